@@ -1,8 +1,14 @@
 //! Abstractions used to configure the MAX7301 hardware.
 
-use expander::Expander;
-use interface::ExpanderInterface;
-use registers::valid_port;
+use crate::error::Error;
+use crate::expander::Expander;
+use crate::interface::ExpanderInterface;
+use crate::registers::{valid_port, valid_transition_port, FIRST_WATCHABLE_PORT};
+
+#[cfg(feature = "async")]
+use crate::expander::AsyncExpander;
+#[cfg(feature = "async")]
+use crate::interface::AsyncExpanderInterface;
 
 fn port_bank_and_offset(port: u8) -> (u8, u8) {
     (valid_port(port) / 4 - 1, port % 4)
@@ -31,7 +37,7 @@ impl From<PortMode> for u8 {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default)]
 pub(crate) struct BankConfig(u8);
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -41,18 +47,12 @@ enum BankConfigStatus {
     Overwrite,
 }
 
-impl Default for BankConfig {
-    fn default() -> Self {
-        Self(0)
-    }
-}
-
 impl BankConfig {
     fn set_port(&mut self, port_offset: u8, cfg: PortMode) {
         match port_offset {
             0..=4 => {
-                let mask = !(0b11u8 << port_offset * 2);
-                let cfg_bits = u8::from(cfg) << port_offset * 2;
+                let mask = !(0b11u8 << (port_offset * 2));
+                let cfg_bits = u8::from(cfg) << (port_offset * 2);
                 self.0 = self.0 & mask | cfg_bits;
             }
             _ => panic!("Config register can only hold 4 ports"),
@@ -60,10 +60,9 @@ impl BankConfig {
     }
     fn keep_mask(&self) -> u8 {
         (0..4)
-            .into_iter()
             .map(|p| {
-                if self.0 & (0b11u8 << p * 2) == 0 {
-                    0b11u8 << p * 2
+                if self.0 & (0b11u8 << (p * 2)) == 0 {
+                    0b11u8 << (p * 2)
                 } else {
                     0u8
                 }
@@ -92,6 +91,7 @@ impl From<BankConfig> for u8 {
 pub(crate) struct ExpanderConfig {
     shutdown: bool,
     transition_detect: bool,
+    pub(crate) transition_mask: u8,
 }
 
 impl Default for ExpanderConfig {
@@ -99,10 +99,18 @@ impl Default for ExpanderConfig {
         Self {
             shutdown: true,
             transition_detect: false,
+            transition_mask: 0,
         }
     }
 }
 
+impl ExpanderConfig {
+    /// Whether the device's shutdown bit is currently set, as last committed by a `Configurator`.
+    pub(crate) fn shutdown(&self) -> bool {
+        self.shutdown
+    }
+}
+
 impl From<ExpanderConfig> for u8 {
     fn from(cfg: ExpanderConfig) -> u8 {
         let shtd = if cfg.shutdown { 0 } else { 0b00000001 };
@@ -133,6 +141,7 @@ impl From<ExpanderConfig> for u8 {
 pub struct Configurator<'e, EI: ExpanderInterface + Send> {
     expander: &'e mut Expander<EI>,
     expander_config_dirty: bool,
+    transition_mask_dirty: bool,
     banks: [BankConfig; 7],
 }
 
@@ -141,6 +150,7 @@ impl<'e, EI: ExpanderInterface + Send> Configurator<'e, EI> {
         Self {
             expander,
             expander_config_dirty: false,
+            transition_mask_dirty: false,
             banks: [BankConfig(0); 7],
         }
     }
@@ -181,19 +191,39 @@ impl<'e, EI: ExpanderInterface + Send> Configurator<'e, EI> {
     }
 
     /// Set the MAX7301's transition detection feature control bit. When `false` the feature is
-    /// disabled; when `true` ports 24 through 31 will be monitored for changes, setting an
-    /// interrupt pin when they are detected. See datasheet for details. Interrupts generated from
-    /// this hardware feature are not managed by this driver.
+    /// disabled; when `true`, the ports selected by [`transition_mask`](Self::transition_mask)
+    /// will be monitored for changes, setting an interrupt pin when they are detected. See
+    /// [`Expander::poll_transitions`] for reading the resulting flag, or the `transition` and
+    /// `transition_detector` modules for higher-level dispatchers built on it.
     pub fn detect_transitions(mut self, enable: bool) -> Self {
         self.expander.config.transition_detect = enable;
         self.expander_config_dirty = true;
         self
     }
 
+    /// Set or clear transition-detection mask bits for `ports`, each of which must be in
+    /// `24..=31`. When `enable` is `true`, a masked port's level change will set the device's
+    /// transition-detection interrupt flag while [`detect_transitions`](Self::detect_transitions)
+    /// is enabled; when `false`, it is excluded even if the global bit is set. See
+    /// [`Expander::poll_transitions`] for reading the resulting flag.
+    pub fn transition_mask(mut self, ports: impl IntoIterator<Item = u8>, enable: bool) -> Self {
+        for port in ports {
+            let port = valid_transition_port(port);
+            let bit = 1 << (port - FIRST_WATCHABLE_PORT);
+            if enable {
+                self.expander.config.transition_mask |= bit;
+            } else {
+                self.expander.config.transition_mask &= !bit;
+            }
+        }
+        self.transition_mask_dirty = true;
+        self
+    }
+
     /// Commit the configuration changes to the MAX7301. The configurator will attempt to update
     /// the device's configuration registers while minimizing bus traffic (avoiding
     /// read-modify-writes when possible, not setting registers that were not changed).
-    pub fn commit(self) -> Result<(), ()> {
+    pub fn commit(self) -> Result<(), Error<EI::Error>> {
         for (bank, bank_config) in self.banks.iter().enumerate() {
             match bank_config.status() {
                 BankConfigStatus::Unchanged => {}
@@ -206,6 +236,10 @@ impl<'e, EI: ExpanderInterface + Send> Configurator<'e, EI> {
                 }
             }
         }
+        if self.transition_mask_dirty {
+            self.expander
+                .write_transition_mask(self.expander.config.transition_mask)?;
+        }
         if self.expander_config_dirty {
             self.expander.write_config()
         } else {
@@ -214,6 +248,91 @@ impl<'e, EI: ExpanderInterface + Send> Configurator<'e, EI> {
     }
 }
 
+/// The `async` counterpart of [`Configurator`], obtained from
+/// [`AsyncExpander::configure`](crate::expander::AsyncExpander::configure). The `port`, `ports`,
+/// `shutdown` and `detect_transitions` builder methods are identical to `Configurator`'s, and
+/// [`commit`](Self::commit) `.await`s the underlying [`AsyncExpanderInterface`]. There is no
+/// `transition_mask` method, so the Transition Detection Mask register is not written.
+#[cfg(feature = "async")]
+#[must_use = "Configuration changes are not applied unless committed"]
+pub struct AsyncConfigurator<'e, EI: AsyncExpanderInterface> {
+    expander: &'e mut AsyncExpander<EI>,
+    expander_config_dirty: bool,
+    banks: [BankConfig; 7],
+}
+
+#[cfg(feature = "async")]
+impl<'e, EI: AsyncExpanderInterface> AsyncConfigurator<'e, EI> {
+    pub(crate) fn new(expander: &'e mut AsyncExpander<EI>) -> Self {
+        Self {
+            expander,
+            expander_config_dirty: false,
+            banks: [BankConfig(0); 7],
+        }
+    }
+
+    fn set_port(&mut self, port: u8, mode: PortMode) {
+        let (bank, offset) = port_bank_and_offset(port);
+        self.banks[bank as usize].set_port(offset, mode);
+    }
+
+    /// See [`Configurator::port`].
+    pub fn port(mut self, port: u8, mode: PortMode) -> Self {
+        self.set_port(port, mode);
+        self
+    }
+
+    /// See [`Configurator::ports`].
+    pub fn ports<I>(mut self, ports: I, mode: PortMode) -> Self
+    where
+        I: IntoIterator<Item = u8>,
+    {
+        for port in ports {
+            self.set_port(port, mode);
+        }
+        self
+    }
+
+    /// See [`Configurator::shutdown`].
+    pub fn shutdown(mut self, enable: bool) -> Self {
+        self.expander.config.shutdown = enable;
+        self.expander_config_dirty = true;
+        self
+    }
+
+    /// See [`Configurator::detect_transitions`].
+    pub fn detect_transitions(mut self, enable: bool) -> Self {
+        self.expander.config.transition_detect = enable;
+        self.expander_config_dirty = true;
+        self
+    }
+
+    /// Commit the configuration changes to the MAX7301. See [`Configurator::commit`] for details;
+    /// this is its `async` counterpart.
+    pub async fn commit(self) -> Result<(), Error<EI::Error>> {
+        for (bank, bank_config) in self.banks.iter().enumerate() {
+            match bank_config.status() {
+                BankConfigStatus::Unchanged => {}
+                BankConfigStatus::Overwrite => {
+                    self.expander
+                        .write_bank_config(bank as u8, *bank_config)
+                        .await?;
+                }
+                BankConfigStatus::ReadModify => {
+                    self.expander
+                        .read_modify_bank_config(bank as u8, |cur| bank_config.merge(cur))
+                        .await?;
+                }
+            }
+        }
+        if self.expander_config_dirty {
+            self.expander.write_config().await
+        } else {
+            Ok(())
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -284,15 +403,19 @@ mod tests {
 
     #[test]
     fn expander_config_disable_shutdown() {
-        let mut expander_config = ExpanderConfig::default();
-        expander_config.shutdown = false;
+        let expander_config = ExpanderConfig {
+            shutdown: false,
+            ..Default::default()
+        };
         assert_eq!(u8::from(expander_config), 0b00000001);
     }
 
     #[test]
     fn expander_config_enable_transition_detect() {
-        let mut expander_config = ExpanderConfig::default();
-        expander_config.transition_detect = true;
+        let expander_config = ExpanderConfig {
+            transition_detect: true,
+            ..Default::default()
+        };
         assert_eq!(u8::from(expander_config), 0b10000000);
     }
 }
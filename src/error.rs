@@ -0,0 +1,29 @@
+//! The driver's shared fallible-operation error type.
+
+/// The error type returned by fallible operations against the MAX7301, parameterized over `E`,
+/// the underlying [`ExpanderInterface`](crate::interface::ExpanderInterface)'s bus error type.
+///
+/// `no_std`/`panic = "abort"` targets cannot recover from a panic, so conditions that could
+/// otherwise have been a `panic!` (e.g. reading a transactional pin that has never been
+/// refreshed) are surfaced here instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Error<E> {
+    /// The underlying bus transaction failed.
+    Bus(E),
+    /// A transactional I/O adapter (e.g. [`TransactionalIO`](crate::expander::transactional::TransactionalIO))
+    /// was asked to read the port number given here, but it has not yet been loaded into the
+    /// adapter's cache by a `refresh` call.
+    UnrefreshedRead(u8),
+}
+
+/// Lets `Error<E>` stand in as the associated `Error` of the `embedded-hal` 1.0 digital traits
+/// (see `expander::pin`'s `hal1` module), which only require a `kind()` classification rather than
+/// a specific variant match. Neither variant maps onto a more specific [`ErrorKind`](crate::hal::digital::ErrorKind),
+/// so both report `Other`. `embedded-hal` 1.0 is always a dependency regardless of the `hal02`
+/// feature, so this impl is unconditional; the `typestate` pins' methods bound on it under either
+/// feature.
+impl<E: core::fmt::Debug> crate::hal::digital::Error for Error<E> {
+    fn kind(&self) -> crate::hal::digital::ErrorKind {
+        crate::hal::digital::ErrorKind::Other
+    }
+}
@@ -0,0 +1,242 @@
+//! Register-shadow caching I/O adapter.
+
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::config::PortMode;
+use crate::error::Error;
+use crate::expander::pin::{ExpanderIO, PortPin};
+use crate::expander::typestate::{Floating, Output, PullUp, ReconfigurablePortIO, TypedPin};
+use crate::expander::Expander;
+use crate::interface::ExpanderInterface;
+use crate::mutex::IOMutex;
+use crate::registers::valid_port;
+
+const FIRST_PORT: u8 = 4;
+const LAST_PORT: u8 = 31;
+
+/// This I/O adapter keeps an in-memory shadow of all 28 ports (4-31) and serves `PortPin` reads
+/// from it, instead of hitting the bus on every access the way [`ImmediateIO`](super::immediate::ImmediateIO)
+/// does. Writes accumulate in the shadow too, and are only pushed out to the hardware when
+/// [`flush`](Self::flush) is called (or a [`Transaction`] guard is dropped), at which point
+/// `flush` writes each fixed 8-port window (starting at ports 4, 12, 20 and 28) that holds a dirty
+/// port with one `Register::PortRange` write, instead of one bus access per port.
+///
+/// Call [`refresh`](Self::refresh) to (re)load the shadow from hardware; between `refresh` calls,
+/// reads are served purely from the cache.
+pub struct CachedIO<M, EI>
+where
+    M: IOMutex<Expander<EI>>,
+    EI: ExpanderInterface + Send,
+{
+    expander: M,
+    shadow: AtomicU32,
+    dirty: AtomicU32,
+    _ei: PhantomData<EI>,
+}
+
+// Unsafety: see the identical impl on `TransactionalIO`. `PhantomData<EI>` is only here to shut up
+// the unused type parameter error; the actual `EI` is owned by the `Expander` inside the mutex,
+// which re-instates `Sync`-ness.
+unsafe impl<M, EI> Sync for CachedIO<M, EI>
+where
+    M: IOMutex<Expander<EI>>,
+    EI: ExpanderInterface + Send,
+{
+}
+
+impl<M, EI> CachedIO<M, EI>
+where
+    M: IOMutex<Expander<EI>>,
+    EI: ExpanderInterface + Send,
+{
+    pub(crate) fn new(expander: Expander<EI>) -> Self {
+        Self {
+            expander: M::new(expander),
+            shadow: AtomicU32::default(),
+            dirty: AtomicU32::default(),
+            _ei: PhantomData,
+        }
+    }
+
+    /// Create a `PortPin` corresponding to one of the ports on the MAX7301. Reads and writes on
+    /// the returned `PortPin` only touch the in-memory shadow; see [`refresh`](Self::refresh) and
+    /// [`flush`](Self::flush) to synchronize it with the hardware.
+    pub fn port_pin<'io>(&'io self, port: u8) -> PortPin<'io, Self> {
+        PortPin::new(self, valid_port(port))
+    }
+
+    /// Reload the shadow of all 28 ports from the hardware, 8 ports at a time. Any pending
+    /// (unflushed) writes are discarded.
+    pub fn refresh(&self) -> Result<(), Error<EI::Error>> {
+        let mut shadow = 0u32;
+        let mut start = FIRST_PORT;
+        while start <= LAST_PORT {
+            let bits = self.expander.lock(|ex| ex.read_ports(start))?;
+            shadow |= (bits as u32) << (start - FIRST_PORT);
+            start += 8;
+        }
+        self.shadow.store(shadow, Ordering::Relaxed);
+        self.dirty.store(0, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Push any pending writes out to the hardware. Writes are coalesced: every 8-port register
+    /// window (the MAX7301's `Register::PortRange` granularity) that contains at least one dirty
+    /// port is written in a single bus transaction, carrying along any other ports in that same
+    /// window from the shadow.
+    pub fn flush(&self) -> Result<(), Error<EI::Error>> {
+        let dirty = self.dirty.swap(0, Ordering::AcqRel);
+        let shadow = self.shadow.load(Ordering::Relaxed);
+        let mut start = FIRST_PORT;
+        while start <= LAST_PORT {
+            let window_mask = 0xFFu32 << (start - FIRST_PORT);
+            if dirty & window_mask != 0 {
+                let bits = (shadow >> (start - FIRST_PORT)) as u8;
+                self.expander.lock(|ex| ex.write_ports(start, bits))?;
+            }
+            start += 8;
+        }
+        Ok(())
+    }
+
+    /// Begin an RAII transaction that flushes any pending writes when it is dropped, so a batch
+    /// of `PortPin` writes (e.g. driving all the segments of a display) can be made to always
+    /// result in a flush without the caller having to remember to call it explicitly.
+    pub fn transaction<'io>(&'io self) -> Transaction<'io, M, EI> {
+        Transaction(self)
+    }
+
+    /// Reconfigure `port` as a push-pull output and return a compile-time-checked pin for it, so
+    /// that only `set_high`/`set_low` are available until it's converted to a different mode.
+    pub fn output_pin<'io>(
+        &'io self,
+        port: u8,
+    ) -> Result<TypedPin<'io, Self, Output>, Error<EI::Error>> {
+        self.set_port_mode(valid_port(port), PortMode::Output)?;
+        Ok(TypedPin::new(self.port_pin(port)))
+    }
+
+    /// Reconfigure `port` as a floating input and return a compile-time-checked pin for it, so
+    /// that only `is_high`/`is_low` are available until it's converted to a different mode.
+    pub fn input_floating_pin<'io>(
+        &'io self,
+        port: u8,
+    ) -> Result<TypedPin<'io, Self, Floating>, Error<EI::Error>> {
+        self.set_port_mode(valid_port(port), PortMode::InputFloating)?;
+        Ok(TypedPin::new(self.port_pin(port)))
+    }
+
+    /// Reconfigure `port` as an input with a weak pull-up and return a compile-time-checked pin
+    /// for it, so that only `is_high`/`is_low` are available until it's converted to a different
+    /// mode.
+    pub fn input_pullup_pin<'io>(
+        &'io self,
+        port: u8,
+    ) -> Result<TypedPin<'io, Self, PullUp>, Error<EI::Error>> {
+        self.set_port_mode(valid_port(port), PortMode::InputPullup)?;
+        Ok(TypedPin::new(self.port_pin(port)))
+    }
+}
+
+impl<M, EI> ReconfigurablePortIO for CachedIO<M, EI>
+where
+    M: IOMutex<Expander<EI>>,
+    EI: ExpanderInterface + Send,
+{
+    fn set_port_mode(&self, port: u8, mode: PortMode) -> Result<(), Self::Error> {
+        self.expander.lock(|ex| ex.configure().port(port, mode).commit())
+    }
+}
+
+impl<M, EI> ExpanderIO for CachedIO<M, EI>
+where
+    M: IOMutex<Expander<EI>>,
+    EI: ExpanderInterface + Send,
+{
+    type Error = Error<EI::Error>;
+
+    fn write_port(&self, port: u8, bit: bool) -> Result<(), Error<EI::Error>> {
+        let idx = port - FIRST_PORT;
+        if bit {
+            self.shadow.fetch_or(1 << idx, Ordering::Relaxed);
+        } else {
+            self.shadow.fetch_and(!(1 << idx), Ordering::Relaxed);
+        }
+        self.dirty.fetch_or(1 << idx, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn read_port(&self, port: u8) -> Result<bool, Error<EI::Error>> {
+        let idx = port - FIRST_PORT;
+        Ok(self.shadow.load(Ordering::Relaxed) & (1 << idx) != 0)
+    }
+}
+
+/// An RAII transaction guard returned by [`CachedIO::transaction`]. Dropping it calls
+/// [`CachedIO::flush`], ignoring any error (use `flush` directly if the result matters).
+pub struct Transaction<'io, M, EI>(&'io CachedIO<M, EI>)
+where
+    M: IOMutex<Expander<EI>>,
+    EI: ExpanderInterface + Send;
+
+impl<'io, M, EI> Drop for Transaction<'io, M, EI>
+where
+    M: IOMutex<Expander<EI>>,
+    EI: ExpanderInterface + Send,
+{
+    fn drop(&mut self) {
+        let _ = self.0.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expander::Expander;
+    #[cfg(feature = "hal02")]
+    use crate::hal02::digital::v2::OutputPin;
+    #[cfg(not(feature = "hal02"))]
+    use crate::hal::digital::OutputPin;
+    use crate::interface::test_spy::{SemanticTestSpyInterface, TestPort};
+    use crate::mutex::DefaultMutex;
+    use std::vec;
+    use std::vec::Vec;
+
+    #[test]
+    fn flush_coalesces_consecutive_writes_into_one_range_write() {
+        let ei = SemanticTestSpyInterface::new(vec![false; 32 - 4]);
+        let io = Expander::new(ei.split()).into_cached::<DefaultMutex<_>>();
+
+        {
+            let _txn = io.transaction();
+            let mut pins = (4..=11).map(|p| io.port_pin(p)).collect::<Vec<_>>();
+            for pin in pins.iter_mut() {
+                pin.set_high().unwrap();
+            }
+        }
+
+        assert_eq!(
+            ei.peek_all()[0..8],
+            [TestPort::BlindWrite(true); 8],
+            "{:?}",
+            ei.peek_all()
+        );
+    }
+
+    #[test]
+    fn flush_only_touches_windows_with_dirty_ports() {
+        let ei = SemanticTestSpyInterface::new(vec![false; 32 - 4]);
+        let io = Expander::new(ei.split()).into_cached::<DefaultMutex<_>>();
+
+        let mut pin_twelve = io.port_pin(12);
+        pin_twelve.set_high().unwrap();
+        assert!(io.flush().is_ok());
+
+        assert_eq!(ei.peek_all()[12 - 4], TestPort::BlindWrite(true));
+        for (idx, port) in ei.peek_all().iter().enumerate() {
+            if !(8..16).contains(&idx) {
+                assert_eq!(*port, TestPort::Reset(false));
+            }
+        }
+    }
+}
@@ -2,16 +2,19 @@
 
 use core::marker::PhantomData;
 
-use expander::pin::{ExpanderIO, PortPin};
-use expander::Expander;
-use interface::ExpanderInterface;
-use mutex::IOMutex;
-use registers::valid_port;
+use crate::config::PortMode;
+use crate::error::Error;
+use crate::expander::pin::{self, ExpanderIO, Pins, PortPin, Variant};
+use crate::expander::typestate::{Floating, Output, PullUp, ReconfigurablePortIO, TypedPin};
+use crate::expander::Expander;
+use crate::interface::ExpanderInterface;
+use crate::mutex::IOMutex;
+use crate::registers::valid_port;
 
 /// This I/O adapter captures the `Expander` and provides a factory for generating GPIO pins that
 /// implement `InputPin` and `OutputPin` traits. Each such pin will immediately issue a bus
 /// transaction to get or set the value every time any pin is accessed.
-pub struct ImmediateIO<M, EI>(M, PhantomData<EI>)
+pub struct ImmediateIO<M, EI>(M, Variant, PhantomData<EI>)
 where
     M: IOMutex<Expander<EI>>,
     EI: ExpanderInterface + Send;
@@ -22,7 +25,8 @@ where
     EI: ExpanderInterface + Send,
 {
     pub(crate) fn new(expander: Expander<EI>) -> Self {
-        ImmediateIO(M::new(expander), PhantomData)
+        let variant = expander.variant();
+        ImmediateIO(M::new(expander), variant, PhantomData)
     }
 
     // cortex-m Mutex doesn't support this operation.
@@ -38,6 +42,72 @@ where
     pub fn port_pin<'io>(&'io self, port: u8) -> PortPin<'io, Self> {
         PortPin::new(self, valid_port(port))
     }
+
+    /// Split this adapter into a [`Pins`] struct with one individually-owned `PortPin` field per
+    /// physical I/O port (`p4` through `p31`). Unlike `port_pin`, which lets two call sites both
+    /// grab a `PortPin` for the same port, each field here can only be moved out once, so
+    /// downstream code can be handed pins by value without risking accidental double-ownership of
+    /// a port.
+    pub fn split<'io>(&'io self) -> Pins<'io, Self> {
+        pin::split(self, self.1)
+    }
+
+    /// Reconfigure `port` as a push-pull output and return a compile-time-checked pin for it, so
+    /// that only `set_high`/`set_low` are available until it's converted to a different mode.
+    pub fn output_pin<'io>(
+        &'io self,
+        port: u8,
+    ) -> Result<TypedPin<'io, Self, Output>, Error<EI::Error>> {
+        self.set_port_mode(valid_port(port), PortMode::Output)?;
+        Ok(TypedPin::new(self.port_pin(port)))
+    }
+
+    /// Reconfigure `port` as a floating input and return a compile-time-checked pin for it, so
+    /// that only `is_high`/`is_low` are available until it's converted to a different mode.
+    pub fn input_floating_pin<'io>(
+        &'io self,
+        port: u8,
+    ) -> Result<TypedPin<'io, Self, Floating>, Error<EI::Error>> {
+        self.set_port_mode(valid_port(port), PortMode::InputFloating)?;
+        Ok(TypedPin::new(self.port_pin(port)))
+    }
+
+    /// Reconfigure `port` as an input with a weak pull-up and return a compile-time-checked pin
+    /// for it, so that only `is_high`/`is_low` are available until it's converted to a different
+    /// mode.
+    pub fn input_pullup_pin<'io>(
+        &'io self,
+        port: u8,
+    ) -> Result<TypedPin<'io, Self, PullUp>, Error<EI::Error>> {
+        self.set_port_mode(valid_port(port), PortMode::InputPullup)?;
+        Ok(TypedPin::new(self.port_pin(port)))
+    }
+}
+
+impl<M, EI> ReconfigurablePortIO for ImmediateIO<M, EI>
+where
+    M: IOMutex<Expander<EI>>,
+    EI: ExpanderInterface + Send,
+{
+    fn set_port_mode(&self, port: u8, mode: PortMode) -> Result<(), Self::Error> {
+        self.0.lock(|ex| ex.configure().port(port, mode).commit())
+    }
+}
+
+/// If every pin in `pins` falls within some single 8-port register window, return that window's
+/// start port (the lowest port among `pins`); otherwise `None`.
+fn single_window<IO: ExpanderIO>(pins: &[&PortPin<IO>]) -> Option<u8> {
+    let mut min = None;
+    let mut max = None;
+    for pin in pins {
+        let port = pin.port();
+        min = Some(min.map_or(port, |m: u8| m.min(port)));
+        max = Some(max.map_or(port, |m: u8| m.max(port)));
+    }
+    match (min, max) {
+        (Some(min), Some(max)) if max - min < 8 => Some(min),
+        _ => None,
+    }
 }
 
 impl<M, EI> ExpanderIO for ImmediateIO<M, EI>
@@ -45,22 +115,78 @@ where
     M: IOMutex<Expander<EI>>,
     EI: ExpanderInterface + Send,
 {
-    type Error = EI::Error;
+    type Error = Error<EI::Error>;
 
-    fn write_port(&self, port: u8, bit: bool) -> Result<(), EI::Error> {
+    fn write_port(&self, port: u8, bit: bool) -> Result<(), Error<EI::Error>> {
         self.0.lock(|ex| ex.write_port(port, bit))
     }
-    fn read_port(&self, port: u8) -> Result<bool, EI::Error> {
+    fn read_port(&self, port: u8) -> Result<bool, Error<EI::Error>> {
         self.0.lock(|ex| ex.read_port(port))
     }
+
+    fn write_multiple(
+        &self,
+        pins: &[&PortPin<Self>],
+        bits: &[bool],
+    ) -> Result<(), Error<EI::Error>> {
+        assert_eq!(pins.len(), bits.len());
+        self.0.lock(|ex| match single_window(pins) {
+            Some(start) => {
+                let mut value = ex.read_ports(start)?;
+                for (pin, &bit) in pins.iter().zip(bits) {
+                    let idx = pin.port() - start;
+                    if bit {
+                        value |= 1 << idx;
+                    } else {
+                        value &= !(1 << idx);
+                    }
+                }
+                ex.write_ports(start, value)
+            }
+            None => {
+                for (pin, &bit) in pins.iter().zip(bits) {
+                    ex.write_port(pin.port(), bit)?;
+                }
+                Ok(())
+            }
+        })
+    }
+
+    fn read_multiple(
+        &self,
+        pins: &[&PortPin<Self>],
+        out: &mut [bool],
+    ) -> Result<(), Error<EI::Error>> {
+        assert_eq!(pins.len(), out.len());
+        self.0.lock(|ex| match single_window(pins) {
+            Some(start) => {
+                let value = ex.read_ports(start)?;
+                for (pin, slot) in pins.iter().zip(out.iter_mut()) {
+                    *slot = value & (1 << (pin.port() - start)) != 0;
+                }
+                Ok(())
+            }
+            None => {
+                for (pin, slot) in pins.iter().zip(out.iter_mut()) {
+                    *slot = ex.read_port(pin.port())?;
+                }
+                Ok(())
+            }
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use expander::Expander;
-    use hal::digital::v2::{InputPin, OutputPin};
-    use interface::test_spy::{TestRegister as TR, TestSpyInterface};
-    use mutex::DefaultMutex;
+    use crate::expander::pin::ExpanderIO;
+    use crate::expander::Expander;
+    #[cfg(feature = "hal02")]
+    use crate::hal02::digital::v2::{InputPin, OutputPin};
+    #[cfg(not(feature = "hal02"))]
+    use crate::hal::digital::{InputPin, OutputPin};
+    use crate::interface::test_spy::{TestRegister as TR, TestSpyInterface};
+    use crate::mutex::DefaultMutex;
+    use std::vec;
 
     #[test]
     fn single_pin_write() {
@@ -76,7 +202,9 @@ mod tests {
     fn single_pin_read() {
         let mut ei = TestSpyInterface::new();
         let io = Expander::new(ei.split()).into_immediate::<DefaultMutex<_>>();
-        let pin_twelve = io.port_pin(12);
+        // `hal02`'s `InputPin::is_high` takes `&self`, so `mut` is only needed under `hal1`.
+        #[allow(unused_mut)]
+        let mut pin_twelve = io.port_pin(12);
 
         ei.set(0x2C, TR::ResetValue(0x00));
         assert_eq!(pin_twelve.is_high(), Ok(false));
@@ -91,7 +219,9 @@ mod tests {
         let io = Expander::new(ei.split()).into_immediate::<DefaultMutex<_>>();
         let mut pin_twelve = io.port_pin(12);
         let mut pin_sixteen = io.port_pin(16);
-        let pin_twenty = io.port_pin(20);
+        // `hal02`'s `InputPin::is_low` takes `&self`, so `mut` is only needed under `hal1`.
+        #[allow(unused_mut)]
+        let mut pin_twenty = io.port_pin(20);
 
         ei.set(0x34, TR::ResetValue(0x01));
         assert!(pin_twelve.set_high().is_ok());
@@ -100,4 +230,84 @@ mod tests {
         assert_eq!(ei.get(0x2C), TR::WrittenValue(0x01));
         assert_eq!(ei.get(0x30), TR::WrittenValue(0x00));
     }
+
+    #[test]
+    fn write_multiple_within_one_window_collapses_to_one_range_write() {
+        let ei = TestSpyInterface::new();
+        let io = Expander::new(ei.split()).into_immediate::<DefaultMutex<_>>();
+        let pin_four = io.port_pin(4);
+        let pin_six = io.port_pin(6);
+
+        assert!(io
+            .write_multiple(&[&pin_four, &pin_six], &[true, true])
+            .is_ok());
+        // Ports 4 and 6 fall in the same PortRange(4) window; only that one register is touched.
+        assert_eq!(ei.get(0x44), TR::WrittenValue(0b0000_0101));
+        assert_eq!(ei.reads(), vec![0x44]);
+    }
+
+    #[test]
+    fn write_multiple_spanning_windows_writes_each_port_singly() {
+        let ei = TestSpyInterface::new();
+        let io = Expander::new(ei.split()).into_immediate::<DefaultMutex<_>>();
+        let pin_four = io.port_pin(4);
+        let pin_sixteen = io.port_pin(16);
+
+        assert!(io
+            .write_multiple(&[&pin_four, &pin_sixteen], &[true, true])
+            .is_ok());
+        assert_eq!(ei.get(0x24), TR::WrittenValue(0x01));
+        assert_eq!(ei.get(0x30), TR::WrittenValue(0x01));
+    }
+
+    #[test]
+    fn read_multiple_within_one_window_collapses_to_one_range_read() {
+        let mut ei = TestSpyInterface::new();
+        ei.set(0x44, TR::ResetValue(0b0000_0100));
+        let io = Expander::new(ei.split()).into_immediate::<DefaultMutex<_>>();
+        let pin_four = io.port_pin(4);
+        let pin_six = io.port_pin(6);
+        let mut out = [false; 2];
+
+        assert!(io.read_multiple(&[&pin_four, &pin_six], &mut out).is_ok());
+        assert_eq!(out, [false, true]);
+        assert_eq!(ei.reads(), vec![0x44]);
+    }
+
+    #[test]
+    fn typed_output_pin_configures_and_writes() {
+        let ei = TestSpyInterface::new();
+        let io = Expander::new(ei.split()).into_immediate::<DefaultMutex<_>>();
+
+        let mut lamp = io.output_pin(4).expect("reconfigure as output");
+        assert_eq!(ei.get(0x09), TR::WrittenValue(0b10101001));
+        assert!(lamp.set_high().is_ok());
+        assert_eq!(ei.get(0x24), TR::WrittenValue(0x01));
+    }
+
+    #[test]
+    fn split_gives_independently_ownable_pins_per_port() {
+        let ei = TestSpyInterface::new();
+        let io = Expander::new(ei.split()).into_immediate::<DefaultMutex<_>>();
+        let pins = io.split();
+
+        let mut red = pins.p4;
+        let mut green = pins.p6;
+        assert!(red.set_high().is_ok());
+        assert!(green.set_low().is_ok());
+        assert_eq!(ei.get(0x24), TR::WrittenValue(0x01));
+        assert_eq!(ei.get(0x26), TR::WrittenValue(0x00));
+    }
+
+    #[test]
+    fn typed_pin_mode_transition_reconfigures_and_retypes() {
+        let ei = TestSpyInterface::new();
+        let io = Expander::new(ei.split()).into_immediate::<DefaultMutex<_>>();
+
+        let sensor = io.input_floating_pin(4).expect("reconfigure as input");
+        let mut lamp = sensor.into_output().expect("reconfigure as output");
+        assert_eq!(ei.get(0x09), TR::WrittenValue(0b10101001));
+        assert!(lamp.set_low().is_ok());
+        assert_eq!(ei.get(0x24), TR::WrittenValue(0x00));
+    }
 }
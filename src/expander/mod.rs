@@ -1,32 +1,59 @@
 //! The port expander device API. This provides the `Expander` type which is a direct abstraction
 //! of the MAX7301. It allows direct use of all operations available on the device.
 
-use config::{BankConfig, Configurator, ExpanderConfig};
-use expander::immediate::ImmediateIO;
-use expander::transactional::TransactionalIO;
-use interface::ExpanderInterface;
-use mutex::IOMutex;
-use registers::Register;
+use crate::config::{BankConfig, Configurator, ExpanderConfig};
+use crate::error::Error;
+use crate::expander::cached::CachedIO;
+use crate::expander::immediate::ImmediateIO;
+use crate::expander::pin::Variant;
+use crate::expander::transactional::TransactionalIO;
+use crate::interface::ExpanderInterface;
+use crate::mutex::IOMutex;
+use crate::registers::{Register, FIRST_WATCHABLE_PORT};
 
+#[cfg(feature = "async")]
+use crate::config::AsyncConfigurator;
+#[cfg(feature = "async")]
+use crate::expander::transactional::AsyncTransactionalIO;
+#[cfg(feature = "async")]
+use crate::interface::AsyncExpanderInterface;
+
+pub mod cached;
 pub mod immediate;
 pub mod pin;
 pub mod transactional;
+pub mod transition;
+#[cfg(feature = "async")]
+pub mod transition_detector;
+pub mod typestate;
 
 /// The port expander device itself.
 pub struct Expander<EI: ExpanderInterface> {
     iface: EI,
     pub(crate) config: ExpanderConfig,
+    transition_prev: u8,
+    variant: Variant,
 }
 
 impl<EI: ExpanderInterface + Send> Expander<EI> {
-    /// Create a new `Expander`.
+    /// Create a new `Expander` for a 28-pin device (ports 4-31 all brought out). Use
+    /// [`Expander::with_variant`] to drive a 20-pin device instead.
     ///
     /// Takes ownership of the `ExpanderInterface` which it should use to communicate with the
     /// MAX7301.
     pub fn new(iface: EI) -> Self {
+        Self::with_variant(iface, Variant::TwentyEightPin)
+    }
+
+    /// Create a new `Expander`, specifying which package variant is wired up. This only affects
+    /// which fields [`Pins`](crate::expander::pin::Pins) exposes after `split()`; every other
+    /// method behaves identically regardless of `variant`.
+    pub fn with_variant(iface: EI, variant: Variant) -> Self {
         Self {
             iface,
             config: ExpanderConfig::default(),
+            transition_prev: 0,
+            variant,
         }
     }
 
@@ -60,11 +87,21 @@ impl<EI: ExpanderInterface + Send> Expander<EI> {
         TransactionalIO::new(self)
     }
 
+    /// Convert this expander into a register-shadow caching I/O adapter.
+    ///
+    /// Like transactional mode, this batches bus traffic; unlike it, `refresh` reloads every port
+    /// rather than only those with an issued `PortPin`, reads never fail on a port that has not
+    /// been refreshed, and `flush` writes whole fixed 8-port windows. See [`CachedIO`] for detail.
+    pub fn into_cached<M: IOMutex<Self>>(self) -> CachedIO<M, EI> {
+        CachedIO::new(self)
+    }
+
     /// Perform a read of the current value of a single I/O port on the expander.
-    pub fn read_port(&mut self, port: u8) -> Result<bool, ()> {
+    pub fn read_port(&mut self, port: u8) -> Result<bool, Error<EI::Error>> {
         self.iface
             .read_register(Register::SinglePort(port).into())
             .map(|v| v == 0x01)
+            .map_err(Error::Bus)
     }
 
     /// Perform a read of the current value of 8 consecutive I/O ports on the expander in a single
@@ -75,17 +112,20 @@ impl<EI: ExpanderInterface + Send> Expander<EI> {
     /// `u8` where the LSB is the value read from `start_port`, and each higher bit is the 7 ports
     /// following it in ascending order. If any of the bits would correspond to a port higher than
     /// 31, then those bits will be unset.
-    pub fn read_ports(&mut self, start_port: u8) -> Result<u8, ()> {
+    pub fn read_ports(&mut self, start_port: u8) -> Result<u8, Error<EI::Error>> {
         self.iface
             .read_register(Register::PortRange(start_port).into())
+            .map_err(Error::Bus)
     }
 
     /// Write a value to a single I/O port on the expander.
-    pub fn write_port(&mut self, port: u8, bit: bool) -> Result<(), ()> {
-        self.iface.write_register(
-            Register::SinglePort(port).into(),
-            if bit { 0x01 } else { 0x00 },
-        )
+    pub fn write_port(&mut self, port: u8, bit: bool) -> Result<(), Error<EI::Error>> {
+        self.iface
+            .write_register(
+                Register::SinglePort(port).into(),
+                if bit { 0x01 } else { 0x00 },
+            )
+            .map_err(Error::Bus)
     }
 
     /// Write a value to 8 consecutive I/O ports on the expander in a single bus transaction.
@@ -95,37 +135,215 @@ impl<EI: ExpanderInterface + Send> Expander<EI> {
     /// where the LSB is the value to write to `start_port`, and each higher bit is the 7 ports
     /// following it in ascending order. If any of the bits would correspond to a port higher than
     /// 31, then those bits will be ignored.
-    pub fn write_ports(&mut self, start_port: u8, bits: u8) -> Result<(), ()> {
+    pub fn write_ports(&mut self, start_port: u8, bits: u8) -> Result<(), Error<EI::Error>> {
         self.iface
             .write_register(Register::PortRange(start_port).into(), bits)
+            .map_err(Error::Bus)
     }
 
-    pub(crate) fn write_config(&mut self) -> Result<(), ()> {
+    pub(crate) fn write_config(&mut self) -> Result<(), Error<EI::Error>> {
         self.iface
             .write_register(Register::Configuration.into(), self.config.clone().into())
+            .map_err(Error::Bus)
     }
 
-    pub(crate) fn write_bank_config(&mut self, bank: u8, cfg: BankConfig) -> Result<(), ()> {
+    pub(crate) fn write_bank_config(
+        &mut self,
+        bank: u8,
+        cfg: BankConfig,
+    ) -> Result<(), Error<EI::Error>> {
         self.iface
             .write_register(Register::BankConfig(bank).into(), cfg.into())
+            .map_err(Error::Bus)
+    }
+
+    /// Write the Transition Detection Mask register, which selects which of ports 24-31
+    /// contribute to the transition-detection interrupt flag when it is enabled.
+    pub(crate) fn write_transition_mask(&mut self, mask: u8) -> Result<(), Error<EI::Error>> {
+        self.iface
+            .write_register(Register::TransitionDetectMask.into(), mask)
+            .map_err(Error::Bus)
+    }
+
+    /// Read the raw Configuration register. Per the datasheet, reading this register is what
+    /// samples and clears the transition-detection interrupt flag when that feature is enabled.
+    pub(crate) fn read_config(&mut self) -> Result<u8, Error<EI::Error>> {
+        self.iface
+            .read_register(Register::Configuration.into())
+            .map_err(Error::Bus)
+    }
+
+    /// Sample and clear the transition-detection flag, returning a bitmask of which of ports
+    /// 24-31 changed level since the last call (LSB = port 24).
+    ///
+    /// Reading the Configuration register is what latches and clears the hardware flag, so the
+    /// Configuration read that this makes before reading ports 24-31 is destructive: each call
+    /// both reports and re-arms the interrupt, and calling
+    /// it again before a reported port has actually changed will not report it a second time.
+    /// `detect_transitions(true)` must be committed, and the ports of interest included via
+    /// `Configurator::transition_mask`, for changes on them to be reflected here.
+    pub fn poll_transitions(&mut self) -> Result<u8, Error<EI::Error>> {
+        self.read_config()?;
+        let levels = self
+            .iface
+            .read_register(Register::PortRange(FIRST_WATCHABLE_PORT).into())
+            .map_err(Error::Bus)?
+            & self.config.transition_mask;
+        let changed = levels ^ self.transition_prev;
+        self.transition_prev = levels;
+        Ok(changed)
+    }
+
+    /// The port levels, restricted to the watchable range and masked by `transition_mask`, as of
+    /// the last [`poll_transitions`](Self::poll_transitions) call. This is the same snapshot
+    /// `poll_transitions` diffs against;
+    /// [`transition::TransitionDispatcher`](crate::expander::transition::TransitionDispatcher) uses
+    /// it to report the current level alongside which ports changed, without keeping its own copy
+    /// of the state `Expander` already tracks.
+    pub(crate) fn transition_levels(&self) -> u8 {
+        self.transition_prev
+    }
+
+    /// Whether the device's shutdown bit is currently set, as last set through a `Configurator`.
+    pub(crate) fn is_shutdown(&self) -> bool {
+        self.config.shutdown()
+    }
+
+    /// Which package variant this `Expander` was constructed for. See
+    /// [`Expander::with_variant`].
+    pub(crate) fn variant(&self) -> Variant {
+        self.variant
     }
 
     pub(crate) fn read_modify_bank_config(
         &mut self,
         bank: u8,
         f: impl Fn(u8) -> BankConfig,
-    ) -> Result<(), ()> {
+    ) -> Result<(), Error<EI::Error>> {
+        let addr = Register::BankConfig(bank).into();
+        let current = self.iface.read_register(addr).map_err(Error::Bus)?;
+        self.iface
+            .write_register(addr, f(current).into())
+            .map_err(Error::Bus)
+    }
+}
+
+/// The `async` counterpart of [`Expander`], for communicating with the MAX7301 (or its I2C
+/// sibling) over an [`AsyncExpanderInterface`] under an async executor such as Embassy.
+///
+/// Like [`transactional::AsyncTransactionalIO`], this has no `IOMutex` parameter: the underlying
+/// interface is held directly rather than behind a lock, so an `AsyncExpander` (and anything built
+/// from it) is meant to be driven from a single task.
+#[cfg(feature = "async")]
+pub struct AsyncExpander<EI: AsyncExpanderInterface> {
+    iface: EI,
+    pub(crate) config: ExpanderConfig,
+}
+
+#[cfg(feature = "async")]
+impl<EI: AsyncExpanderInterface> AsyncExpander<EI> {
+    /// Create a new `AsyncExpander`. See [`Expander::new`] for details; this is its `async`
+    /// counterpart.
+    pub fn new(iface: EI) -> Self {
+        Self {
+            iface,
+            config: ExpanderConfig::default(),
+        }
+    }
+
+    /// Begin (re)configuring the port expander hardware by returning an [`AsyncConfigurator`].
+    /// See [`Expander::configure`] for details; this is its `async` counterpart.
+    pub fn configure<'e>(&'e mut self) -> AsyncConfigurator<'e, EI> {
+        AsyncConfigurator::new(self)
+    }
+
+    /// Convert this expander into an async transactional I/O adapter.
+    ///
+    /// Unlike [`Expander::into_transactional`], there is no `IOMutex` parameter to choose; see
+    /// [`AsyncTransactionalIO`] for why.
+    pub fn into_transactional(self) -> AsyncTransactionalIO<EI> {
+        AsyncTransactionalIO::new(self.iface)
+    }
+
+    /// Perform a read of the current value of a single I/O port on the expander. See
+    /// [`Expander::read_port`] for details; this is its `async` counterpart.
+    pub async fn read_port(&mut self, port: u8) -> Result<bool, Error<EI::Error>> {
+        self.iface
+            .read_register(Register::SinglePort(port).into())
+            .await
+            .map(|v| v == 0x01)
+            .map_err(Error::Bus)
+    }
+
+    /// Perform a read of 8 consecutive I/O ports on the expander in a single bus transaction. See
+    /// [`Expander::read_ports`] for details; this is its `async` counterpart.
+    pub async fn read_ports(&mut self, start_port: u8) -> Result<u8, Error<EI::Error>> {
+        self.iface
+            .read_register(Register::PortRange(start_port).into())
+            .await
+            .map_err(Error::Bus)
+    }
+
+    /// Write a value to a single I/O port on the expander. See [`Expander::write_port`] for
+    /// details; this is its `async` counterpart.
+    pub async fn write_port(&mut self, port: u8, bit: bool) -> Result<(), Error<EI::Error>> {
+        self.iface
+            .write_register(
+                Register::SinglePort(port).into(),
+                if bit { 0x01 } else { 0x00 },
+            )
+            .await
+            .map_err(Error::Bus)
+    }
+
+    /// Write a value to 8 consecutive I/O ports on the expander in a single bus transaction. See
+    /// [`Expander::write_ports`] for details; this is its `async` counterpart.
+    pub async fn write_ports(&mut self, start_port: u8, bits: u8) -> Result<(), Error<EI::Error>> {
+        self.iface
+            .write_register(Register::PortRange(start_port).into(), bits)
+            .await
+            .map_err(Error::Bus)
+    }
+
+    pub(crate) async fn write_config(&mut self) -> Result<(), Error<EI::Error>> {
+        self.iface
+            .write_register(Register::Configuration.into(), self.config.clone().into())
+            .await
+            .map_err(Error::Bus)
+    }
+
+    pub(crate) async fn write_bank_config(
+        &mut self,
+        bank: u8,
+        cfg: BankConfig,
+    ) -> Result<(), Error<EI::Error>> {
+        self.iface
+            .write_register(Register::BankConfig(bank).into(), cfg.into())
+            .await
+            .map_err(Error::Bus)
+    }
+
+    pub(crate) async fn read_modify_bank_config(
+        &mut self,
+        bank: u8,
+        f: impl Fn(u8) -> BankConfig,
+    ) -> Result<(), Error<EI::Error>> {
         let addr = Register::BankConfig(bank).into();
-        let current = self.iface.read_register(addr)?;
-        self.iface.write_register(addr, f(current).into())
+        let current = self.iface.read_register(addr).await.map_err(Error::Bus)?;
+        self.iface
+            .write_register(addr, f(current).into())
+            .await
+            .map_err(Error::Bus)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use config::PortMode;
-    use interface::test_spy::{TestRegister as TR, TestSpyInterface};
+    use std::vec;
+    use std::vec::Vec;
+    use crate::config::PortMode;
+    use crate::interface::test_spy::{TestRegister as TR, TestSpyInterface};
 
     #[test]
     fn expander_configure_noop() {
@@ -134,7 +352,6 @@ mod tests {
         assert!(ex.configure().commit().is_ok());
         assert_eq!(
             (0x09..0x10)
-                .into_iter()
                 .map(|a| ei.get(a))
                 .collect::<Vec<_>>(),
             vec![TR::ResetValue(0b10101010); 7]
@@ -157,6 +374,53 @@ mod tests {
         assert_eq!(ei.get(0x04), TR::WrittenValue(0b10000000));
     }
 
+    #[test]
+    fn expander_configure_transition_mask() {
+        let ei = TestSpyInterface::new();
+        let mut ex = Expander::new(ei.split());
+        assert!(ex
+            .configure()
+            .transition_mask(24..=26, true)
+            .commit()
+            .is_ok());
+        assert_eq!(ei.get(0x06), TR::WrittenValue(0b0000_0111));
+    }
+
+    #[test]
+    fn expander_configure_transition_mask_clear() {
+        let ei = TestSpyInterface::new();
+        let mut ex = Expander::new(ei.split());
+        assert!(ex
+            .configure()
+            .transition_mask(24..=31, true)
+            .commit()
+            .is_ok());
+        assert!(ex
+            .configure()
+            .transition_mask([25], false)
+            .commit()
+            .is_ok());
+        assert_eq!(ei.get(0x06), TR::WrittenValue(0b1111_1101));
+    }
+
+    #[test]
+    fn expander_poll_transitions_reports_changes_since_last_poll() {
+        let mut ei = TestSpyInterface::new();
+        let mut ex = Expander::new(ei.split());
+        ex.configure()
+            .transition_mask(24..=31, true)
+            .detect_transitions(true)
+            .commit()
+            .unwrap();
+
+        ei.set(0x40 + 24, TR::ResetValue(0b0000_0001));
+        assert_eq!(ex.poll_transitions(), Ok(0b0000_0001));
+        assert_eq!(ex.poll_transitions(), Ok(0b0000_0000));
+
+        ei.set(0x40 + 24, TR::ResetValue(0b0000_0011));
+        assert_eq!(ex.poll_transitions(), Ok(0b0000_0010));
+    }
+
     #[test]
     fn expander_configure_port_single_read_modify() {
         let ei = TestSpyInterface::new();
@@ -164,7 +428,6 @@ mod tests {
         assert!(ex.configure().port(4, PortMode::Output).commit().is_ok());
         assert_eq!(
             (0x09..0x10)
-                .into_iter()
                 .map(|a| ei.get(a))
                 .collect::<Vec<_>>(),
             vec![
@@ -191,7 +454,6 @@ mod tests {
             .is_ok());
         assert_eq!(
             (0x09..0x10)
-                .into_iter()
                 .map(|a| ei.get(a))
                 .collect::<Vec<_>>(),
             vec![
@@ -218,7 +480,6 @@ mod tests {
             .is_ok());
         assert_eq!(
             (0x09..0x10)
-                .into_iter()
                 .map(|a| ei.get(a))
                 .collect::<Vec<_>>(),
             vec![
@@ -245,7 +506,6 @@ mod tests {
             .is_ok());
         assert_eq!(
             (0x09..0x10)
-                .into_iter()
                 .map(|a| ei.get(a))
                 .collect::<Vec<_>>(),
             vec![
@@ -273,7 +533,6 @@ mod tests {
             .is_ok());
         assert_eq!(
             (0x09..0x10)
-                .into_iter()
                 .map(|a| ei.get(a))
                 .collect::<Vec<_>>(),
             vec![
@@ -1,7 +1,9 @@
 //! APIs for interacting with I/O pins on the MAX7301 through an `embedded-hal` API.
-
-use hal::digital::v2::InputPin;
-use hal::digital::v2::OutputPin;
+//!
+//! By default, `PortPin` implements the `embedded-hal` 1.0 digital traits (`InputPin`,
+//! `OutputPin`, `StatefulOutputPin`). Enable the `hal02` feature to get the legacy `embedded-hal`
+//! 0.2 (`v2`) traits instead, via an `embedded-hal-0.2` compatibility shim, for platforms that
+//! haven't migrated yet.
 
 /// An indirection between I/O pin abstractions and the expander itself, which allows selection
 /// between transactional reads and writes, which reduce bus traffic and latency, and
@@ -22,6 +24,45 @@ pub trait ExpanderIO {
     /// as an output, the last set value will be read; if it is configured as an input, the
     /// logic level of the externally applied signal will be read.
     fn read_port(&self, port: u8) -> Result<bool, Self::Error>;
+
+    /// Write the values of several I/O ports.
+    ///
+    /// The default implementation just issues one `write_port` per pin in turn, with no atomicity
+    /// guarantee: another access to the expander (from another pin, or another thread sharing the
+    /// same adapter) may be interleaved between the individual writes. An adapter that needs this
+    /// to be atomic has to provide that itself; `ImmediateIO` overrides it to do the whole batch
+    /// under one lock of its mutex, and, when all of `pins` fall within one 8-port register window,
+    /// as a single `Register::PortRange` read followed by a single `Register::PortRange` write.
+    fn write_multiple(&self, pins: &[&PortPin<Self>], bits: &[bool]) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+    {
+        assert_eq!(pins.len(), bits.len());
+        for (pin, &bit) in pins.iter().zip(bits) {
+            self.write_port(pin.port(), bit)?;
+        }
+        Ok(())
+    }
+
+    /// Read the values of several I/O ports, writing results into `out` (which must be the same
+    /// length as `pins`, in the same order).
+    ///
+    /// The default implementation just issues one `read_port` per pin in turn, with no atomicity
+    /// guarantee: another access to the expander (from another pin, or another thread sharing the
+    /// same adapter) may be interleaved between the individual reads. An adapter that needs this to
+    /// be atomic has to provide that itself; `ImmediateIO` overrides it so that, when all of `pins`
+    /// fall within one 8-port register window, the whole batch collapses into a single
+    /// `Register::PortRange` bus transaction with nothing else able to interleave.
+    fn read_multiple(&self, pins: &[&PortPin<Self>], out: &mut [bool]) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+    {
+        assert_eq!(pins.len(), out.len());
+        for (pin, slot) in pins.iter().zip(out.iter_mut()) {
+            *slot = self.read_port(pin.port())?;
+        }
+        Ok(())
+    }
 }
 
 /// A single I/O pin on the MAX7301. These implement the `embedded-hal` traits for GPIO pins, so
@@ -36,26 +77,242 @@ impl<'io, IO: ExpanderIO> PortPin<'io, IO> {
     pub(crate) fn new(io: &'io IO, port: u8) -> Self {
         Self { io, port }
     }
+
+    /// The port number on the MAX7301 this pin corresponds to.
+    pub(crate) fn port(&self) -> u8 {
+        self.port
+    }
+
+    /// The I/O adapter backing this pin.
+    pub(crate) fn io(&self) -> &'io IO {
+        self.io
+    }
+}
+
+/// Which package variant of the MAX7301/MAX7300 is wired up. The two variants share one register
+/// map, but the 20-pin package does not bring ports 12-19 out to a physical pin at all, so a
+/// `Pins` struct split from a 20-pin device leaves those fields absent rather than offering
+/// [`PortPin`]s for ports that don't exist on the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variant {
+    /// The 20-pin package: ports 4-11 and 20-31 are brought out; ports 12-19 are not present.
+    TwentyPin,
+    /// The 28-pin package: all of ports 4-31 are brought out.
+    TwentyEightPin,
+}
+
+/// The complete set of individually-owned I/O pins on a MAX7301, returned by an I/O adapter's
+/// `split()` method. Each present field is a distinct [`PortPin`] that can be moved out of the
+/// struct and handed off on its own (e.g. as a chip-select or reset line for some other
+/// `embedded-hal` driver), which makes it impossible by construction for two call sites to both
+/// end up holding a pin for the same port.
+///
+/// Ports 12-19 are only brought out on the 28-pin package, so those fields are `None` when
+/// [`split`] is called with [`Variant::TwentyPin`].
+#[allow(missing_docs)]
+pub struct Pins<'io, IO: ExpanderIO> {
+    pub p4: PortPin<'io, IO>,
+    pub p5: PortPin<'io, IO>,
+    pub p6: PortPin<'io, IO>,
+    pub p7: PortPin<'io, IO>,
+    pub p8: PortPin<'io, IO>,
+    pub p9: PortPin<'io, IO>,
+    pub p10: PortPin<'io, IO>,
+    pub p11: PortPin<'io, IO>,
+    pub p12: Option<PortPin<'io, IO>>,
+    pub p13: Option<PortPin<'io, IO>>,
+    pub p14: Option<PortPin<'io, IO>>,
+    pub p15: Option<PortPin<'io, IO>>,
+    pub p16: Option<PortPin<'io, IO>>,
+    pub p17: Option<PortPin<'io, IO>>,
+    pub p18: Option<PortPin<'io, IO>>,
+    pub p19: Option<PortPin<'io, IO>>,
+    pub p20: PortPin<'io, IO>,
+    pub p21: PortPin<'io, IO>,
+    pub p22: PortPin<'io, IO>,
+    pub p23: PortPin<'io, IO>,
+    pub p24: PortPin<'io, IO>,
+    pub p25: PortPin<'io, IO>,
+    pub p26: PortPin<'io, IO>,
+    pub p27: PortPin<'io, IO>,
+    pub p28: PortPin<'io, IO>,
+    pub p29: PortPin<'io, IO>,
+    pub p30: PortPin<'io, IO>,
+    pub p31: PortPin<'io, IO>,
+}
+
+pub(crate) fn split<'io, IO: ExpanderIO>(io: &'io IO, variant: Variant) -> Pins<'io, IO> {
+    let has_12_19 = matches!(variant, Variant::TwentyEightPin);
+    Pins {
+        p4: PortPin::new(io, 4),
+        p5: PortPin::new(io, 5),
+        p6: PortPin::new(io, 6),
+        p7: PortPin::new(io, 7),
+        p8: PortPin::new(io, 8),
+        p9: PortPin::new(io, 9),
+        p10: PortPin::new(io, 10),
+        p11: PortPin::new(io, 11),
+        p12: has_12_19.then(|| PortPin::new(io, 12)),
+        p13: has_12_19.then(|| PortPin::new(io, 13)),
+        p14: has_12_19.then(|| PortPin::new(io, 14)),
+        p15: has_12_19.then(|| PortPin::new(io, 15)),
+        p16: has_12_19.then(|| PortPin::new(io, 16)),
+        p17: has_12_19.then(|| PortPin::new(io, 17)),
+        p18: has_12_19.then(|| PortPin::new(io, 18)),
+        p19: has_12_19.then(|| PortPin::new(io, 19)),
+        p20: PortPin::new(io, 20),
+        p21: PortPin::new(io, 21),
+        p22: PortPin::new(io, 22),
+        p23: PortPin::new(io, 23),
+        p24: PortPin::new(io, 24),
+        p25: PortPin::new(io, 25),
+        p26: PortPin::new(io, 26),
+        p27: PortPin::new(io, 27),
+        p28: PortPin::new(io, 28),
+        p29: PortPin::new(io, 29),
+        p30: PortPin::new(io, 30),
+        p31: PortPin::new(io, 31),
+    }
+}
+
+/// `embedded-hal` 1.0 digital trait impls for `PortPin` (the default; see the module
+/// documentation for the `hal02` compatibility feature).
+#[cfg(not(feature = "hal02"))]
+mod hal1 {
+    use crate::hal::digital::{Error as DigitalError, ErrorType, InputPin, OutputPin, StatefulOutputPin};
+
+    use super::{ExpanderIO, PortPin};
+
+    impl<'io, IO: ExpanderIO> ErrorType for PortPin<'io, IO>
+    where
+        IO::Error: DigitalError,
+    {
+        type Error = IO::Error;
+    }
+
+    impl<'io, IO: ExpanderIO> OutputPin for PortPin<'io, IO>
+    where
+        IO::Error: DigitalError,
+    {
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.io.write_port(self.port, true)
+        }
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.io.write_port(self.port, false)
+        }
+    }
+
+    impl<'io, IO: ExpanderIO> InputPin for PortPin<'io, IO>
+    where
+        IO::Error: DigitalError,
+    {
+        fn is_high(&mut self) -> Result<bool, Self::Error> {
+            self.io.read_port(self.port)
+        }
+        fn is_low(&mut self) -> Result<bool, Self::Error> {
+            self.io.read_port(self.port).map(|hi| !hi)
+        }
+    }
+
+    /// Reading a port configured as an output yields the last value written to it (see
+    /// [`ExpanderIO::read_port`]), so `is_set_high`/`is_set_low` are implemented the same way
+    /// `is_high`/`is_low` are. For adapters with a write-back cache, like `TransactionalIO`, this
+    /// answers from the cache with no bus traffic; `ImmediateIO` still issues a read. `toggle()`
+    /// comes from the trait's default implementation and falls out of this for free.
+    impl<'io, IO: ExpanderIO> StatefulOutputPin for PortPin<'io, IO>
+    where
+        IO::Error: DigitalError,
+    {
+        fn is_set_high(&mut self) -> Result<bool, Self::Error> {
+            self.io.read_port(self.port)
+        }
+        fn is_set_low(&mut self) -> Result<bool, Self::Error> {
+            self.io.read_port(self.port).map(|hi| !hi)
+        }
+    }
 }
 
-impl<'io, IO: ExpanderIO> OutputPin for PortPin<'io, IO> {
-    type Error = IO::Error;
+/// Legacy `embedded-hal` 0.2 (`v2`) digital trait impls for `PortPin`, kept for platforms that
+/// haven't migrated to `embedded-hal` 1.0 yet. Enabled by the `hal02` feature, which disables the
+/// 1.0 impls in [`hal1`] since both define the same inherent-looking trait methods.
+#[cfg(feature = "hal02")]
+mod hal02_compat {
+    use crate::hal02::digital::v2::{InputPin, OutputPin};
 
-    fn set_high(&mut self) -> Result<(), Self::Error> {
-        self.io.write_port(self.port, true)
+    use super::{ExpanderIO, PortPin};
+
+    impl<'io, IO: ExpanderIO> OutputPin for PortPin<'io, IO> {
+        type Error = IO::Error;
+
+        fn set_high(&mut self) -> Result<(), Self::Error> {
+            self.io.write_port(self.port, true)
+        }
+        fn set_low(&mut self) -> Result<(), Self::Error> {
+            self.io.write_port(self.port, false)
+        }
     }
-    fn set_low(&mut self) -> Result<(), Self::Error> {
-        self.io.write_port(self.port, false)
+
+    impl<'io, IO: ExpanderIO> InputPin for PortPin<'io, IO> {
+        type Error = IO::Error;
+
+        fn is_high(&self) -> Result<bool, Self::Error> {
+            self.io.read_port(self.port)
+        }
+        fn is_low(&self) -> Result<bool, Self::Error> {
+            self.io.read_port(self.port).map(|hi| !hi)
+        }
     }
 }
 
-impl<'io, IO: ExpanderIO> InputPin for PortPin<'io, IO> {
-    type Error = IO::Error;
+/// The `async` counterpart of [`ExpanderIO`], for I/O adapters whose underlying bus access is
+/// `.await`-able (e.g. an async `SpiInterface`).
+#[cfg(feature = "async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncExpanderIO {
+    /// The type of error that register reads and writes may return.
+    type Error;
+
+    /// Write the value of an I/O port. See [`ExpanderIO::write_port`] for the meaning of the
+    /// arguments.
+    async fn write_port(&self, port: u8, bit: bool) -> Result<(), Self::Error>;
+
+    /// Read the value of an I/O port. See [`ExpanderIO::read_port`] for the meaning of the
+    /// arguments and return value.
+    async fn read_port(&self, port: u8) -> Result<bool, Self::Error>;
+}
+
+/// A single I/O pin on the MAX7301, for use under an async executor. This mirrors [`PortPin`],
+/// but every operation is an `async fn` that `.await`s the underlying [`AsyncExpanderIO`] instead
+/// of blocking on it.
+#[cfg(feature = "async")]
+pub struct AsyncPortPin<'io, IO: AsyncExpanderIO> {
+    io: &'io IO,
+    port: u8,
+}
+
+#[cfg(feature = "async")]
+impl<'io, IO: AsyncExpanderIO> AsyncPortPin<'io, IO> {
+    pub(crate) fn new(io: &'io IO, port: u8) -> Self {
+        Self { io, port }
+    }
+
+    /// Drive the pin high (if configured as an output).
+    pub async fn set_high(&mut self) -> Result<(), IO::Error> {
+        self.io.write_port(self.port, true).await
+    }
 
-    fn is_high(&self) -> Result<bool, Self::Error> {
-        self.io.read_port(self.port)
+    /// Drive the pin low (if configured as an output).
+    pub async fn set_low(&mut self) -> Result<(), IO::Error> {
+        self.io.write_port(self.port, false).await
     }
-    fn is_low(&self) -> Result<bool, Self::Error> {
-        self.io.read_port(self.port).map(|hi| !hi)
+
+    /// Read whether the pin is currently at a logic high level.
+    pub async fn is_high(&self) -> Result<bool, IO::Error> {
+        self.io.read_port(self.port).await
+    }
+
+    /// Read whether the pin is currently at a logic low level.
+    pub async fn is_low(&self) -> Result<bool, IO::Error> {
+        self.io.read_port(self.port).await.map(|hi| !hi)
     }
 }
@@ -3,11 +3,30 @@
 use core::marker::PhantomData;
 use core::sync::atomic::{AtomicUsize, Ordering};
 
-use expander::pin::{ExpanderIO, PortPin};
-use expander::Expander;
-use interface::ExpanderInterface;
-use mutex::IOMutex;
-use registers::valid_port;
+#[cfg(feature = "async")]
+use core::cell::RefCell;
+#[cfg(feature = "async")]
+use core::future::Future;
+#[cfg(feature = "async")]
+use core::pin::Pin;
+#[cfg(feature = "async")]
+use core::task::{Context, Poll, Waker};
+
+use crate::config::PortMode;
+use crate::error::Error;
+use crate::expander::pin::{ExpanderIO, PortPin};
+use crate::expander::typestate::{Floating, Output, PullUp, ReconfigurablePortIO, TypedPin};
+use crate::expander::Expander;
+use crate::interface::ExpanderInterface;
+use crate::mutex::IOMutex;
+use crate::registers::valid_port;
+
+#[cfg(feature = "async")]
+use crate::expander::pin::{AsyncExpanderIO, AsyncPortPin};
+#[cfg(feature = "async")]
+use crate::interface::AsyncExpanderInterface;
+#[cfg(feature = "async")]
+use crate::registers::Register;
 
 /// Control how `TransactionalIO::write_back` will batch writes to modified pins.
 pub enum Strategy {
@@ -47,13 +66,17 @@ pub enum Strategy {
 pub struct TransactionalIO<M, EI>
 where
     M: IOMutex<Expander<EI>>,
-    EI: ExpanderInterface,
+    EI: ExpanderInterface + Send,
 {
     expander: M,
     issued: AtomicUsize,
     cache: AtomicUsize,
     dirty: AtomicUsize,
     fresh: AtomicUsize,
+    #[cfg(feature = "async")]
+    prev_cache: AtomicUsize,
+    #[cfg(feature = "async")]
+    waiters: RefCell<[Option<(WaitCondition, Waker)>; 32]>,
     _ei: PhantomData<EI>,
 }
 
@@ -64,14 +87,14 @@ where
 unsafe impl<M, EI> Sync for TransactionalIO<M, EI>
 where
     M: IOMutex<Expander<EI>>,
-    EI: ExpanderInterface,
+    EI: ExpanderInterface + Send,
 {
 }
 
 impl<M, EI> TransactionalIO<M, EI>
 where
     M: IOMutex<Expander<EI>>,
-    EI: ExpanderInterface,
+    EI: ExpanderInterface + Send,
 {
     pub(crate) fn new(expander: Expander<EI>) -> Self {
         TransactionalIO {
@@ -80,6 +103,10 @@ where
             cache: AtomicUsize::default(),
             dirty: AtomicUsize::default(),
             fresh: AtomicUsize::default(),
+            #[cfg(feature = "async")]
+            prev_cache: AtomicUsize::default(),
+            #[cfg(feature = "async")]
+            waiters: RefCell::new(core::array::from_fn(|_| None)),
             _ei: PhantomData,
         }
     }
@@ -98,7 +125,7 @@ where
     /// from this adapter, updating the values read through their `InputPin` impls. This is done
     /// using batch registers of MAX7301 to reduce bus traffic. All pending `OutputPin` operations
     /// are discarded.
-    pub fn refresh(&self) -> Result<(), ()> {
+    pub fn refresh(&self) -> Result<(), Error<EI::Error>> {
         self.dirty.store(0, Ordering::Release);
         let mut load_buffer = 0usize;
         let mut fresh_buffer = 0usize;
@@ -110,18 +137,30 @@ where
             start_port += skip;
             let port_values = self.expander.lock(|ex| ex.read_ports(start_port as u8))?;
             load_buffer |= (port_values as usize) << start_port;
-            fresh_buffer |= 0xFFusize << start_port;
+            // A window starting above port 24 (e.g. at 28, which only has ports 28-31 in it) runs
+            // past port 31, so naively shifting a full 0xFF in would mark nonexistent ports 32
+            // and up "fresh" too, which then overflows `waiters`'s 32 slots in `wake_waiters`.
+            fresh_buffer |= (0xFFusize << start_port) & 0xFFFF_FFFF;
             ports_to_read &= !0xFFusize;
         }
+        #[cfg(feature = "async")]
+        let prev_fresh = self.fresh.load(Ordering::Relaxed);
+        #[cfg(feature = "async")]
+        let prev_levels = self.prev_cache.load(Ordering::Relaxed);
         self.cache.store(load_buffer, Ordering::Relaxed);
         self.fresh.store(fresh_buffer, Ordering::Relaxed);
+        #[cfg(feature = "async")]
+        {
+            self.wake_waiters(prev_fresh, prev_levels, fresh_buffer, load_buffer);
+            self.prev_cache.store(load_buffer, Ordering::Relaxed);
+        }
         Ok(())
     }
 
     /// Write back any pending `OutputPin` operations to the MAX7301. The strategy used to do this
     /// is controlled by `strategy` (see [`Strategy`] docs for a description of the available
     /// strategies).
-    pub fn write_back(&self, strategy: Strategy) -> Result<(), ()> {
+    pub fn write_back(&self, strategy: Strategy) -> Result<(), Error<EI::Error>> {
         let mut start_port = 0;
         let mut ports_to_write = self.dirty.load(Ordering::Acquire);
         let mut ok_to_write = match strategy {
@@ -150,14 +189,368 @@ where
         self.dirty.store(0, Ordering::Release);
         Ok(())
     }
+
+    /// Reconfigure `port` as a push-pull output and return a compile-time-checked pin for it, so
+    /// that only `set_high`/`set_low` are available until it's converted to a different mode.
+    pub fn output_pin<'io>(
+        &'io self,
+        port: u8,
+    ) -> Result<TypedPin<'io, Self, Output>, Error<EI::Error>> {
+        self.set_port_mode(valid_port(port), PortMode::Output)?;
+        Ok(TypedPin::new(self.port_pin(port)))
+    }
+
+    /// Reconfigure `port` as a floating input and return a compile-time-checked pin for it, so
+    /// that only `is_high`/`is_low` are available until it's converted to a different mode.
+    pub fn input_floating_pin<'io>(
+        &'io self,
+        port: u8,
+    ) -> Result<TypedPin<'io, Self, Floating>, Error<EI::Error>> {
+        self.set_port_mode(valid_port(port), PortMode::InputFloating)?;
+        Ok(TypedPin::new(self.port_pin(port)))
+    }
+
+    /// Reconfigure `port` as an input with a weak pull-up and return a compile-time-checked pin
+    /// for it, so that only `is_high`/`is_low` are available until it's converted to a different
+    /// mode.
+    pub fn input_pullup_pin<'io>(
+        &'io self,
+        port: u8,
+    ) -> Result<TypedPin<'io, Self, PullUp>, Error<EI::Error>> {
+        self.set_port_mode(valid_port(port), PortMode::InputPullup)?;
+        Ok(TypedPin::new(self.port_pin(port)))
+    }
+}
+
+impl<M, EI> ReconfigurablePortIO for TransactionalIO<M, EI>
+where
+    M: IOMutex<Expander<EI>>,
+    EI: ExpanderInterface + Send,
+{
+    fn set_port_mode(&self, port: u8, mode: PortMode) -> Result<(), Self::Error> {
+        self.expander.lock(|ex| ex.configure().port(port, mode).commit())
+    }
 }
 
 impl<M, EI> ExpanderIO for TransactionalIO<M, EI>
 where
     M: IOMutex<Expander<EI>>,
-    EI: ExpanderInterface,
+    EI: ExpanderInterface + Send,
+{
+    type Error = Error<EI::Error>;
+
+    fn write_port(&self, port: u8, bit: bool) -> Result<(), Error<EI::Error>> {
+        let or_bit = 1 << port;
+        if bit {
+            self.cache.fetch_or(or_bit, Ordering::Release);
+        } else {
+            self.cache.fetch_and(!or_bit, Ordering::Release);
+        }
+        self.dirty.fetch_or(or_bit, Ordering::Relaxed);
+        self.fresh.fetch_or(or_bit, Ordering::Relaxed);
+        Ok(())
+    }
+    fn read_port(&self, port: u8) -> Result<bool, Error<EI::Error>> {
+        if self.fresh.load(Ordering::Relaxed) & (1 << port) == 0 {
+            return Err(Error::UnrefreshedRead(port));
+        }
+        Ok(self.cache.load(Ordering::Relaxed) & (1 << port) != 0)
+    }
+}
+
+/// The level or edge condition a [`PortWait`] future is waiting on, as registered by one of
+/// `PortPin::wait_for_high`/`wait_for_low`/`wait_for_rising_edge`/`wait_for_falling_edge`.
+#[cfg(feature = "async")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WaitCondition {
+    High,
+    Low,
+    Rising,
+    Falling,
+}
+
+#[cfg(feature = "async")]
+impl<M, EI> TransactionalIO<M, EI>
+where
+    M: IOMutex<Expander<EI>>,
+    EI: ExpanderInterface + Send,
+{
+    /// The port's level as of the most recent `refresh`, or `None` if it has never been
+    /// refreshed. Unlike `ExpanderIO::read_port`, this never fails on an un-refreshed port; the
+    /// wait API needs to distinguish "not yet known" from a level to poll for it sensibly.
+    fn port_level(&self, port: u8) -> Option<bool> {
+        let bit = 1usize << port;
+        if self.fresh.load(Ordering::Relaxed) & bit == 0 {
+            None
+        } else {
+            Some(self.cache.load(Ordering::Relaxed) & bit != 0)
+        }
+    }
+
+    /// Register `waker` to be woken the next time `refresh` observes `port` satisfying
+    /// `condition`. Only the most recently registered waiter per port is kept; registering again
+    /// (as a polled `Future` does on every pending poll) simply replaces it.
+    fn register_waiter(&self, port: u8, condition: WaitCondition, waker: Waker) {
+        self.waiters.borrow_mut()[port as usize] = Some((condition, waker));
+    }
+
+    /// Called at the end of `refresh` to wake any waiter whose condition is now satisfied.
+    ///
+    /// `prev_fresh`/`prev_levels` are the `fresh`/`cache` snapshots from *before* this `refresh`
+    /// (i.e. the previous call's results); `fresh`/`levels` are the ones this call just produced.
+    /// A port that is fresh now but wasn't fresh before is this pin's first observation since it
+    /// was issued, so edges are suppressed for it (there is no prior level to compare against) and
+    /// only level waiters can be satisfied.
+    fn wake_waiters(&self, prev_fresh: usize, prev_levels: usize, fresh: usize, levels: usize) {
+        let mut waiters = self.waiters.borrow_mut();
+        let mut newly_fresh = fresh;
+        while newly_fresh != 0 {
+            let port = newly_fresh.trailing_zeros() as usize;
+            newly_fresh &= !(1 << port);
+
+            let bit = 1usize << port;
+            let current = levels & bit != 0;
+            let had_prior_sample = prev_fresh & bit != 0;
+            let satisfied = match waiters[port].as_ref().map(|(c, _)| *c) {
+                Some(WaitCondition::High) => current,
+                Some(WaitCondition::Low) => !current,
+                Some(WaitCondition::Rising) => {
+                    had_prior_sample && prev_levels & bit == 0 && current
+                }
+                Some(WaitCondition::Falling) => {
+                    had_prior_sample && prev_levels & bit != 0 && !current
+                }
+                None => false,
+            };
+            if satisfied {
+                if let Some((_, waker)) = waiters[port].take() {
+                    waker.wake();
+                }
+            }
+        }
+    }
+}
+
+/// A `Future` that resolves the next time `TransactionalIO::refresh` observes its port satisfying
+/// the condition it was constructed with. See `PortPin::wait_for_high` and friends.
+///
+/// Since the MAX7301 has no interrupt line for generic GPIO changes (only the dedicated ports
+/// 24-31 transition-detection hardware handled by `transition`/`transition_detector`), nothing
+/// drives this future to completion on its own: a caller is expected to call `refresh`
+/// periodically (e.g. from a timer task) for it to ever resolve.
+#[cfg(feature = "async")]
+pub struct PortWait<'io, M, EI>
+where
+    M: IOMutex<Expander<EI>>,
+    EI: ExpanderInterface + Send,
 {
-    fn write_port(&self, port: u8, bit: bool) {
+    io: &'io TransactionalIO<M, EI>,
+    port: u8,
+    condition: WaitCondition,
+}
+
+#[cfg(feature = "async")]
+impl<'io, M, EI> Future for PortWait<'io, M, EI>
+where
+    M: IOMutex<Expander<EI>>,
+    EI: ExpanderInterface + Send,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let satisfied_now = match self.condition {
+            WaitCondition::High => self.io.port_level(self.port) == Some(true),
+            WaitCondition::Low => self.io.port_level(self.port) == Some(false),
+            WaitCondition::Rising | WaitCondition::Falling => false,
+        };
+        if satisfied_now {
+            Poll::Ready(())
+        } else {
+            self.io
+                .register_waiter(self.port, self.condition, cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<'io, M, EI> PortPin<'io, TransactionalIO<M, EI>>
+where
+    M: IOMutex<Expander<EI>>,
+    EI: ExpanderInterface + Send,
+{
+    /// Resolve the next time `refresh` observes this pin at a logic high level (including
+    /// immediately, if it is already known to be high).
+    pub fn wait_for_high(&self) -> PortWait<'io, M, EI> {
+        PortWait {
+            io: self.io(),
+            port: self.port(),
+            condition: WaitCondition::High,
+        }
+    }
+
+    /// Resolve the next time `refresh` observes this pin at a logic low level (including
+    /// immediately, if it is already known to be low).
+    pub fn wait_for_low(&self) -> PortWait<'io, M, EI> {
+        PortWait {
+            io: self.io(),
+            port: self.port(),
+            condition: WaitCondition::Low,
+        }
+    }
+
+    /// Resolve the next time `refresh` observes this pin transition from low to high.
+    ///
+    /// The very first `refresh` after this pin was issued cannot itself complete this wait, since
+    /// there is no prior sample to compare against.
+    pub fn wait_for_rising_edge(&self) -> PortWait<'io, M, EI> {
+        PortWait {
+            io: self.io(),
+            port: self.port(),
+            condition: WaitCondition::Rising,
+        }
+    }
+
+    /// Resolve the next time `refresh` observes this pin transition from high to low.
+    ///
+    /// The very first `refresh` after this pin was issued cannot itself complete this wait, since
+    /// there is no prior sample to compare against.
+    pub fn wait_for_falling_edge(&self) -> PortWait<'io, M, EI> {
+        PortWait {
+            io: self.io(),
+            port: self.port(),
+            condition: WaitCondition::Falling,
+        }
+    }
+}
+
+/// The `async` counterpart of [`TransactionalIO`], backed by an [`AsyncExpanderInterface`] instead
+/// of a blocking [`ExpanderInterface`].
+///
+/// The batching/segment-coalescing logic over the `issued`/`dirty`/`fresh`/`cache` bitmasks is
+/// identical to [`TransactionalIO`]; only the per-segment register transfers become `.await`
+/// points, so a task yields to the executor while the expander finishes each 8-port transfer
+/// instead of busy-waiting on it. Unlike [`TransactionalIO`], there is no `IOMutex` parameter: the
+/// underlying interface is held in a [`RefCell`], so this adapter is meant to be driven from a
+/// single task (the same restriction [`AsyncPortPin`] already has).
+#[cfg(feature = "async")]
+pub struct AsyncTransactionalIO<EI: AsyncExpanderInterface> {
+    iface: RefCell<EI>,
+    issued: AtomicUsize,
+    cache: AtomicUsize,
+    dirty: AtomicUsize,
+    fresh: AtomicUsize,
+}
+
+#[cfg(feature = "async")]
+impl<EI: AsyncExpanderInterface> AsyncTransactionalIO<EI> {
+    /// Create a new async transactional I/O adapter directly from an [`AsyncExpanderInterface`].
+    ///
+    /// Unlike [`TransactionalIO::new`], there is no `Expander` to convert, since `Expander` only
+    /// wraps the blocking [`ExpanderInterface`]; construct the interface (e.g.
+    /// [`interface::spi_async::AsyncSpiInterface`](crate::interface::spi_async::AsyncSpiInterface))
+    /// and hand it straight to this constructor.
+    pub fn new(iface: EI) -> Self {
+        Self {
+            iface: RefCell::new(iface),
+            issued: AtomicUsize::default(),
+            cache: AtomicUsize::default(),
+            dirty: AtomicUsize::default(),
+            fresh: AtomicUsize::default(),
+        }
+    }
+
+    /// Create an `AsyncPortPin` corresponding to one of the ports on the MAX7301. See
+    /// [`TransactionalIO::port_pin`] for the semantics; this is its `async` counterpart.
+    pub fn port_pin<'io>(&'io self, port: u8) -> AsyncPortPin<'io, Self> {
+        self.issued
+            .fetch_or(1 << valid_port(port), Ordering::Relaxed);
+        AsyncPortPin::new(self, port)
+    }
+
+    /// Refresh the local cache by reading the port values from any outstanding `AsyncPortPin`s
+    /// issued from this adapter. See [`TransactionalIO::refresh`] for the semantics; this is its
+    /// `async` counterpart.
+    ///
+    /// Holding the `RefCell` borrow across the `.await` below is sound because this adapter is
+    /// only ever driven from a single task (see the struct docs); clippy can't see that
+    /// invariant.
+    #[allow(clippy::await_holding_refcell_ref)]
+    pub async fn refresh(&self) -> Result<(), EI::Error> {
+        self.dirty.store(0, Ordering::Release);
+        let mut load_buffer = 0usize;
+        let mut fresh_buffer = 0usize;
+        let mut start_port = 4;
+        let mut ports_to_read = self.issued.load(Ordering::Relaxed) >> start_port;
+        while ports_to_read != 0 {
+            let skip = ports_to_read.trailing_zeros();
+            ports_to_read >>= skip;
+            start_port += skip;
+            let port_values = self
+                .iface
+                .borrow_mut()
+                .read_register(Register::PortRange(start_port as u8).into())
+                .await?;
+            load_buffer |= (port_values as usize) << start_port;
+            // See the blocking `refresh` above: the last window would otherwise mark nonexistent
+            // ports 32-35 "fresh".
+            fresh_buffer |= (0xFFusize << start_port) & 0xFFFF_FFFF;
+            ports_to_read &= !0xFFusize;
+        }
+        self.cache.store(load_buffer, Ordering::Relaxed);
+        self.fresh.store(fresh_buffer, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Write back any pending `AsyncPortPin` writes to the MAX7301. See
+    /// [`TransactionalIO::write_back`] for the semantics; this is its `async` counterpart.
+    ///
+    /// Holding the `RefCell` borrow across the `.await` points below is sound for the same reason
+    /// as in [`refresh`](Self::refresh): this adapter is only ever driven from a single task.
+    #[allow(clippy::await_holding_refcell_ref)]
+    pub async fn write_back(&self, strategy: Strategy) -> Result<(), EI::Error> {
+        let mut start_port = 0;
+        let mut ports_to_write = self.dirty.load(Ordering::Acquire);
+        let mut ok_to_write = match strategy {
+            Strategy::Exact => ports_to_write,
+            Strategy::StompClean => self.fresh.load(Ordering::Acquire),
+            Strategy::StompAny => 0xFFFFFFFC,
+        };
+        let cache = self.cache.load(Ordering::Acquire);
+        while ports_to_write != 0 {
+            let skip = ports_to_write.trailing_zeros();
+            ports_to_write >>= skip;
+            ok_to_write >>= skip;
+            start_port += skip;
+            if ok_to_write & 0xFF == 0xFF {
+                let port_values = (cache >> start_port) as u8;
+                self.iface
+                    .borrow_mut()
+                    .write_register(Register::PortRange(start_port as u8).into(), port_values)
+                    .await?;
+                ports_to_write &= !0xFFusize;
+            } else {
+                let port_value = cache & (1 << start_port) != 0;
+                self.iface
+                    .borrow_mut()
+                    .write_register(
+                        Register::SinglePort(start_port as u8).into(),
+                        if port_value { 0x01 } else { 0x00 },
+                    )
+                    .await?;
+                ports_to_write &= !0x01usize;
+            }
+        }
+        self.dirty.store(0, Ordering::Release);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl<EI: AsyncExpanderInterface> AsyncExpanderIO for AsyncTransactionalIO<EI> {
+    type Error = Error<EI::Error>;
+
+    async fn write_port(&self, port: u8, bit: bool) -> Result<(), Self::Error> {
         let or_bit = 1 << port;
         if bit {
             self.cache.fetch_or(or_bit, Ordering::Release);
@@ -166,22 +559,28 @@ where
         }
         self.dirty.fetch_or(or_bit, Ordering::Relaxed);
         self.fresh.fetch_or(or_bit, Ordering::Relaxed);
+        Ok(())
     }
-    fn read_port(&self, port: u8) -> bool {
+
+    async fn read_port(&self, port: u8) -> Result<bool, Self::Error> {
         if self.fresh.load(Ordering::Relaxed) & (1 << port) == 0 {
-            panic!("Read of un-refreshed port;}")
+            return Err(Error::UnrefreshedRead(port));
         }
-        self.cache.load(Ordering::Relaxed) & (1 << port) != 0
+        Ok(self.cache.load(Ordering::Relaxed) & (1 << port) != 0)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::Strategy;
-    use expander::Expander;
-    use hal::digital::{InputPin, OutputPin};
-    use interface::test_spy::{SemanticTestSpyInterface, TestPort};
-    use mutex::DefaultMutex;
+    use crate::expander::Expander;
+    #[cfg(feature = "hal02")]
+    use crate::hal02::digital::v2::{InputPin, OutputPin};
+    #[cfg(not(feature = "hal02"))]
+    use crate::hal::digital::{InputPin, OutputPin};
+    use crate::interface::test_spy::{SemanticTestSpyInterface, TestPort};
+    use crate::mutex::DefaultMutex;
+    use std::vec::Vec;
     use proptest::collection::vec;
     use proptest::prelude::*;
 
@@ -189,18 +588,17 @@ mod tests {
         #![proptest_config(ProptestConfig::with_cases(2000))]
 
         #[test]
-        fn prop_read_unrefreshed_panics(
+        fn prop_read_unrefreshed_errors(
             reset in vec(any::<bool>(), 32 - 4),
             pin in 4..=31u8
         ) {
-            assert!(std::panic::catch_unwind(|| {
-                let ei = SemanticTestSpyInterface::new(reset);
-                let io = Expander::new(ei.split()).into_transactional::<DefaultMutex<_>>();
-                let any_pin = io.port_pin(pin);
-
-                any_pin.is_high();
-            })
-            .is_err());
+            let ei = SemanticTestSpyInterface::new(reset);
+            let io = Expander::new(ei.split()).into_transactional::<DefaultMutex<_>>();
+            // `hal02`'s `InputPin::is_high` takes `&self`, so `mut` is only needed under `hal1`.
+            #[allow(unused_mut)]
+            let mut any_pin = io.port_pin(pin);
+
+            assert_eq!(any_pin.is_high(), Err(super::Error::UnrefreshedRead(pin)));
         }
 
         #[test]
@@ -210,7 +608,9 @@ mod tests {
         ) {
             let ei = SemanticTestSpyInterface::new(reset.clone());
             let io = Expander::new(ei.split()).into_transactional::<DefaultMutex<_>>();
-            let some_pins = pins
+            // `hal02`'s `InputPin::is_high` takes `&self`, so `mut` is only needed under `hal1`.
+            #[allow(unused_mut)]
+            let mut some_pins = pins
                 .iter()
                 .cloned()
                 .map(|p| io.port_pin(p))
@@ -218,7 +618,7 @@ mod tests {
 
             assert!(io.refresh().is_ok());
             for (idx, pin_nr) in pins.iter().enumerate() {
-                assert_eq!(some_pins[idx].is_high(), reset[*pin_nr as usize - 4]);
+                assert_eq!(some_pins[idx].is_high(), Ok(reset[*pin_nr as usize - 4]));
             }
         }
 
@@ -237,15 +637,14 @@ mod tests {
                 .map(|(p, b)| (p, io.port_pin(p), b))
                 .collect::<Vec<_>>();
             let mut expect = (4..=31)
-                .into_iter()
                 .map(|p| TestPort::Reset(reset[p as usize - 4]))
                 .collect::<Vec<_>>();
 
             for (port, pin, bit) in some_pins.iter_mut() {
                 if *bit {
-                    pin.set_high()
+                    pin.set_high().unwrap();
                 } else {
-                    pin.set_low()
+                    pin.set_low().unwrap();
                 }
                 expect[*port as usize - 4] = TestPort::BlindWrite(*bit);
             }
@@ -268,16 +667,15 @@ mod tests {
                 .map(|(p, b)| (p, io.port_pin(p), b))
                 .collect::<Vec<_>>();
             let mut expect = (4..=31)
-                .into_iter()
                 .map(|p| TestPort::Reset(reset[p as usize - 4]))
                 .collect::<Vec<_>>();
 
             assert!(io.refresh().is_ok());
             for (port, pin, bit) in some_pins.iter_mut() {
                 if *bit {
-                    pin.set_high()
+                    pin.set_high().unwrap();
                 } else {
-                    pin.set_low()
+                    pin.set_low().unwrap();
                 }
                 expect[*port as usize - 4] = TestPort::ReadWrite(*bit);
             }
@@ -300,15 +698,14 @@ mod tests {
                 .map(|(p, b)| (p, io.port_pin(p), b))
                 .collect::<Vec<_>>();
             let mut expect = (4..=31)
-                .into_iter()
                 .map(|p| TestPort::Reset(reset[p as usize - 4]))
                 .collect::<Vec<_>>();
 
             for (port, pin, bit) in some_pins.iter_mut() {
                 if *bit {
-                    pin.set_high()
+                    pin.set_high().unwrap();
                 } else {
-                    pin.set_low()
+                    pin.set_low().unwrap();
                 }
                 expect[*port as usize - 4] = TestPort::BlindWrite(*bit);
             }
@@ -336,19 +733,18 @@ mod tests {
             assert!(io.refresh().is_ok());
             for (port, pin, bit) in some_pins.iter_mut() {
                 if *bit {
-                    pin.set_high()
+                    pin.set_high().unwrap();
                 } else {
-                    pin.set_low()
+                    pin.set_low().unwrap();
                 }
                 expect[*port as usize - 4] = *bit;
             }
             assert!(io.write_back(Strategy::StompClean).is_ok());
             assert_eq!(expect, ei.peek_bits());
             assert!(
-                !ei.peek_all().iter().any(|p| match p {
-                    TestPort::BlindWrite(_) => true,
-                    _ => false,
-                }),
+                !ei.peek_all()
+                    .iter()
+                    .any(|p| matches!(p, TestPort::BlindWrite(_))),
                 "{:?}",
                 ei.peek_all()
             );
@@ -372,9 +768,9 @@ mod tests {
 
             for (port, pin, bit) in some_pins.iter_mut() {
                 if *bit {
-                    pin.set_high()
+                    pin.set_high().unwrap();
                 } else {
-                    pin.set_low()
+                    pin.set_low().unwrap();
                 }
                 expect.insert(*port, *bit);
             }
@@ -407,9 +803,9 @@ mod tests {
             assert!(io.refresh().is_ok());
             for (port, pin, bit) in some_pins.iter_mut() {
                 if *bit {
-                    pin.set_high()
+                    pin.set_high().unwrap();
                 } else {
-                    pin.set_low()
+                    pin.set_low().unwrap();
                 }
                 expect.insert(*port, *bit);
             }
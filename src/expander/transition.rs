@@ -0,0 +1,145 @@
+//! A polling-based dispatcher over the MAX7301's transition-detection hardware, which monitors
+//! ports 24-31 for level changes.
+//!
+//! Unlike [`transition_detector`](super::transition_detector), which awaits a host interrupt pin
+//! directly, this subsystem leaves the decision of *when* to poll up to the caller: wire a host
+//! `InputPin` to the MAX7301's `/INT` output, have your ISR (or main loop) check it, and call
+//! [`TransitionDispatcher::poll_transitions`] whenever it is asserted.
+
+#[cfg(feature = "hal02")]
+use crate::hal02::digital::v2::InputPin;
+#[cfg(not(feature = "hal02"))]
+use crate::hal::digital::InputPin;
+
+use crate::error::Error;
+use crate::expander::Expander;
+use crate::interface::ExpanderInterface;
+use crate::registers::FIRST_WATCHABLE_PORT;
+
+/// Dispatches transition-detection events for ports 24-31, mirroring the way an interrupt
+/// controller turns a raw IRQ line into individually dispatchable events.
+///
+/// Construct one from an `Expander` and a host `InputPin` wired to `/INT`. Call
+/// [`watch`](Self::watch) to select which of ports 24-31 participate, then call
+/// [`poll_transitions`](Self::poll_transitions) (typically from your ISR, after observing `/INT`
+/// asserted via [`interrupt_pending`](Self::interrupt_pending)) to get an iterator of the ports
+/// that changed and their new levels.
+pub struct TransitionDispatcher<EI, INT>
+where
+    EI: ExpanderInterface,
+{
+    expander: Expander<EI>,
+    int: INT,
+}
+
+/// The `(port, new_level)` events yielded by [`TransitionDispatcher::poll_transitions`].
+pub struct TransitionEvents {
+    changed: u8,
+    levels: u8,
+    next_offset: u8,
+}
+
+impl Iterator for TransitionEvents {
+    type Item = (u8, bool);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.next_offset < 8 {
+            let offset = self.next_offset;
+            self.next_offset += 1;
+            if self.changed & (1 << offset) != 0 {
+                return Some((FIRST_WATCHABLE_PORT + offset, self.levels & (1 << offset) != 0));
+            }
+        }
+        None
+    }
+}
+
+impl<EI, INT> TransitionDispatcher<EI, INT>
+where
+    EI: ExpanderInterface + Send,
+    INT: InputPin,
+{
+    /// Create a new dispatcher from an `Expander` and the host `InputPin` wired to `/INT`.
+    ///
+    /// This does not itself arm any ports; call [`watch`](Self::watch) for each port you want to
+    /// monitor.
+    pub fn new(expander: Expander<EI>, int: INT) -> Self {
+        Self { expander, int }
+    }
+
+    /// Arm transition detection on `ports`, each of which must be in `24..=31`.
+    ///
+    /// This sets the corresponding bits in the Transition Detection Mask register and enables the
+    /// global transition-detection bit in the Configuration register, which also forces the banks
+    /// containing the watched ports into an input-capable mode.
+    ///
+    /// Detection is inoperative while the device is in shutdown, so this refuses to arm (without
+    /// touching the device) unless `shutdown(false)` has already been committed through a
+    /// `Configurator`.
+    #[allow(clippy::result_unit_err)]
+    pub fn watch(&mut self, ports: impl IntoIterator<Item = u8>) -> Result<(), ()> {
+        if self.expander.is_shutdown() {
+            return Err(());
+        }
+        self.expander
+            .configure()
+            .transition_mask(ports, true)
+            .detect_transitions(true)
+            .commit()
+            .map_err(|_| ())
+    }
+
+    /// Check whether the host interrupt pin is currently asserted (active low), cheaply, without
+    /// touching the MAX7301's bus at all. An ISR can use this to decide whether it's worth calling
+    /// [`poll_transitions`](Self::poll_transitions).
+    pub fn interrupt_pending(&mut self) -> Result<bool, INT::Error> {
+        self.int.is_low()
+    }
+
+    /// Sample and clear the transition-detection flag, returning an iterator of the watched ports
+    /// that changed since the last call and their new level (`true` = high).
+    ///
+    /// This is a thin wrapper over [`Expander::poll_transitions`], which is what actually reads
+    /// the Configuration register to latch and clear the hardware flag.
+    pub fn poll_transitions(&mut self) -> Result<TransitionEvents, Error<EI::Error>> {
+        let changed = self.expander.poll_transitions()?;
+        let levels = self.expander.transition_levels();
+        Ok(TransitionEvents {
+            changed,
+            levels,
+            next_offset: 0,
+        })
+    }
+
+    /// Consume the dispatcher, returning the underlying `Expander` and interrupt pin.
+    pub fn release(self) -> (Expander<EI>, INT) {
+        (self.expander, self.int)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registers::valid_transition_port;
+    use std::vec;
+    use std::vec::Vec;
+
+    #[test]
+    #[should_panic]
+    fn rejects_out_of_range_port() {
+        valid_transition_port(4);
+    }
+
+    #[test]
+    fn events_only_yield_changed_watched_ports() {
+        let events = TransitionEvents {
+            changed: 0b0000_0101,
+            levels: 0b0000_0001,
+            next_offset: 0,
+        };
+        assert_eq!(
+            events.collect::<Vec<_>>(),
+            vec![(FIRST_WATCHABLE_PORT, true), (FIRST_WATCHABLE_PORT + 2, false)]
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! An interrupt-driven async "wait for change" API over the MAX7301's transition detection
+//! hardware, which monitors ports 24-31 for level changes.
+
+use embedded_hal_async::digital::Wait;
+
+use crate::expander::Expander;
+use crate::interface::ExpanderInterface;
+use crate::registers::valid_transition_port;
+
+/// Which edge(s) of a port's level to treat as a transition in [`TransitionDetector::watch`].
+///
+/// The MAX7301's transition-detection hardware itself does not distinguish direction; it only
+/// latches "this masked port's level changed". `Edge` exists so the API reads naturally at the
+/// call site, but every variant currently arms the same mask bit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Rising,
+    Falling,
+    Both,
+}
+
+/// The error type returned by [`TransitionDetector`] operations, unifying a bus error from the
+/// expander with an error from the host interrupt pin.
+#[derive(Debug)]
+pub enum Error<IE> {
+    /// An error occurred communicating with the MAX7301 over the bus.
+    Bus,
+    /// An error occurred waiting on the host interrupt pin.
+    Interrupt(IE),
+}
+
+/// Turns the hardware IRQ line on the MAX7301's `/INT` output into a dispatchable, `.await`-able
+/// stream of port-change events, the same way an interrupt controller layer turns a raw IRQ into
+/// events a task can wait on.
+///
+/// Construct a `TransitionDetector` from an `Expander` and a host GPIO input pin wired to the
+/// MAX7301's `/INT` output that implements the `embedded-hal-async` `Wait` trait. Call
+/// [`watch`](Self::watch) for each port you want to monitor (`24..=31` only), then `.await`
+/// [`next_event`](Self::next_event) to sleep until one of the watched ports changes.
+pub struct TransitionDetector<EI, INT>
+where
+    EI: ExpanderInterface,
+{
+    expander: Expander<EI>,
+    int: INT,
+}
+
+impl<EI, INT> TransitionDetector<EI, INT>
+where
+    EI: ExpanderInterface + Send,
+    INT: Wait,
+{
+    /// Create a new detector from an `Expander` and the host interrupt pin wired to `/INT`.
+    ///
+    /// This does not itself arm any ports; call [`watch`](Self::watch) for each port you want to
+    /// monitor.
+    pub fn new(expander: Expander<EI>, int: INT) -> Self {
+        Self { expander, int }
+    }
+
+    /// Arm transition detection on `port`, which must be in `24..=31`.
+    ///
+    /// This sets the corresponding bit in the Transition Detection Mask register and enables the
+    /// global transition-detection bit in the Configuration register, which also forces the bank
+    /// containing `port` into an input-capable mode. `edge` is accepted for API symmetry with
+    /// host-side edge-triggered GPIO APIs; see [`Edge`] for why it does not change the device
+    /// programming.
+    pub fn watch(&mut self, port: u8, _edge: Edge) -> Result<(), Error<INT::Error>> {
+        let port = valid_transition_port(port);
+        self.expander
+            .configure()
+            .transition_mask([port], true)
+            .detect_transitions(true)
+            .commit()
+            .map_err(|_| Error::Bus)
+    }
+
+    /// Wait for the next transition on any watched port.
+    ///
+    /// This awaits a falling edge on the host interrupt pin, then calls
+    /// [`Expander::poll_transitions`] to read the Configuration register (which is what clears the
+    /// transition-detection flag on the device) and diff the watched ports against their levels as
+    /// of the last call, so this call both reports and re-arms the interrupt. The returned bitmask
+    /// has a bit set (LSB = port 24) for each watched port that changed level since the last call;
+    /// a set bit does not say which direction it moved, only that it did (see [`Edge`]).
+    pub async fn next_event(&mut self) -> Result<u8, Error<INT::Error>> {
+        self.int
+            .wait_for_falling_edge()
+            .await
+            .map_err(Error::Interrupt)?;
+        self.expander.poll_transitions().map_err(|_| Error::Bus)
+    }
+
+    /// Consume the detector, returning the underlying `Expander` and interrupt pin.
+    pub fn release(self) -> (Expander<EI>, INT) {
+        (self.expander, self.int)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hal::digital::{ErrorKind, ErrorType};
+    use crate::interface::test_spy::TestSpyInterface;
+    use core::future::Future;
+    use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+    #[test]
+    #[should_panic]
+    fn rejects_out_of_range_port() {
+        valid_transition_port(4);
+    }
+
+    #[test]
+    fn accepts_edges_of_watchable_range() {
+        assert_eq!(valid_transition_port(24), 24);
+        assert_eq!(valid_transition_port(31), 31);
+    }
+
+    /// A host interrupt pin mock whose edge-waits all resolve immediately, so tests can drive
+    /// `next_event` without a real async executor.
+    struct ImmediateInt;
+
+    impl ErrorType for ImmediateInt {
+        type Error = ErrorKind;
+    }
+
+    impl Wait for ImmediateInt {
+        async fn wait_for_high(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+        async fn wait_for_low(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+        async fn wait_for_rising_edge(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+        async fn wait_for_falling_edge(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+        async fn wait_for_any_edge(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    fn noop_waker() -> Waker {
+        fn clone(_: *const ()) -> RawWaker {
+            raw_waker()
+        }
+        fn no_op(_: *const ()) {}
+        fn raw_waker() -> RawWaker {
+            static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, no_op, no_op, no_op);
+            RawWaker::new(core::ptr::null(), &VTABLE)
+        }
+        unsafe { Waker::from_raw(raw_waker()) }
+    }
+
+    /// Every future in these tests resolves on the first poll (`ImmediateInt`'s waits never
+    /// pend), so this just polls once instead of pulling in a real executor.
+    fn block_on<F: Future>(fut: F) -> F::Output {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+        let mut fut = core::pin::pin!(fut);
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(v) => v,
+            Poll::Pending => panic!("test future unexpectedly pended"),
+        }
+    }
+
+    #[test]
+    fn next_event_reads_configuration_register_once_to_clear_the_latch() {
+        let ei = TestSpyInterface::new();
+        let mut detector = TransitionDetector::new(Expander::new(ei.split()), ImmediateInt);
+        detector.watch(24, Edge::Both).unwrap();
+
+        block_on(detector.next_event()).unwrap();
+
+        assert_eq!(ei.reads().iter().filter(|&&a| a == 0x04).count(), 1);
+    }
+
+    #[test]
+    fn next_event_reports_changed_ports_not_raw_levels() {
+        use crate::interface::test_spy::TestRegister as TR;
+
+        let mut ei = TestSpyInterface::new();
+        let mut detector = TransitionDetector::new(Expander::new(ei.split()), ImmediateInt);
+        detector.watch(24, Edge::Both).unwrap();
+        detector.watch(25, Edge::Both).unwrap();
+
+        // Ports 24 and 25 both start high; this seeds the detector's level snapshot.
+        ei.set(0x40 + 24, TR::ResetValue(0b0000_0011));
+        block_on(detector.next_event()).unwrap();
+
+        // Port 24 falls and unwatched port 26 rises; port 25 stays high throughout. Only port
+        // 24's bit should be set in the changed mask.
+        ei.set(0x40 + 24, TR::ResetValue(0b0000_0110));
+        let changed = block_on(detector.next_event()).unwrap();
+
+        assert_eq!(changed, 0b0000_0001);
+    }
+}
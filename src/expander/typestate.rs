@@ -0,0 +1,151 @@
+//! Compile-time type-state I/O pins reflecting `PortMode`.
+//!
+//! A plain [`PortPin`] does not check its direction at all: writing a pin configured as an input
+//! just silently has no effect on the hardware. `TypedPin<IO, MODE>` instead carries
+//! its `PortMode` in its type, so `set_high`/`set_low` only exist on an output pin and
+//! `is_high`/`is_low` only on an input pin; calling the wrong one is a compile error rather than a
+//! runtime surprise.
+
+use core::marker::PhantomData;
+
+#[cfg(feature = "hal02")]
+use crate::hal02::digital::v2::{InputPin, OutputPin};
+#[cfg(not(feature = "hal02"))]
+use crate::hal::digital::{InputPin, OutputPin};
+
+use crate::config::PortMode;
+use crate::expander::pin::{ExpanderIO, PortPin};
+
+/// Marker type for a pin configured as a push-pull output.
+pub struct Output;
+
+/// Marker type for a pin configured as a floating input.
+pub struct Floating;
+
+/// Marker type for a pin configured as an input with a weak pull-up.
+pub struct PullUp;
+
+/// An I/O adapter that can reconfigure an individual port's mode on demand, which is what lets
+/// [`TypedPin::into_output`] and friends issue the bank-config write a mode change requires.
+/// `ImmediateIO`, `TransactionalIO`, and `CachedIO` all implement this.
+pub trait ReconfigurablePortIO: ExpanderIO {
+    /// Reconfigure `port`'s bank-config bits to `mode`, issuing a bus transaction.
+    fn set_port_mode(&self, port: u8, mode: PortMode) -> Result<(), Self::Error>;
+}
+
+/// A single I/O pin on the MAX7301 whose direction (`MODE`) is tracked at compile time. See the
+/// module documentation for why that's useful, and [`downgrade`](Self::downgrade) for converting
+/// back to a dynamically-checked [`PortPin`] when that's what you need instead.
+pub struct TypedPin<'io, IO: ExpanderIO, MODE> {
+    pin: PortPin<'io, IO>,
+    _mode: PhantomData<MODE>,
+}
+
+impl<'io, IO: ExpanderIO, MODE> TypedPin<'io, IO, MODE> {
+    pub(crate) fn new(pin: PortPin<'io, IO>) -> Self {
+        Self {
+            pin,
+            _mode: PhantomData,
+        }
+    }
+
+    /// Erase the compile-time mode, yielding the ordinary dynamically-checked `PortPin` for code
+    /// that needs runtime flexibility (e.g. storing pins of mixed modes in one collection).
+    pub fn downgrade(self) -> PortPin<'io, IO> {
+        self.pin
+    }
+
+    /// Alias for [`downgrade`](Self::downgrade).
+    pub fn into_dynamic(self) -> PortPin<'io, IO> {
+        self.downgrade()
+    }
+}
+
+impl<'io, IO: ReconfigurablePortIO> TypedPin<'io, IO, Output>
+where
+    IO::Error: crate::hal::digital::Error,
+{
+    /// Drive the pin high.
+    pub fn set_high(&mut self) -> Result<(), IO::Error> {
+        self.pin.set_high()
+    }
+
+    /// Drive the pin low.
+    pub fn set_low(&mut self) -> Result<(), IO::Error> {
+        self.pin.set_low()
+    }
+
+    /// Reconfigure this pin as a floating input, consuming it and returning the re-typed pin.
+    pub fn into_input_floating(self) -> Result<TypedPin<'io, IO, Floating>, IO::Error> {
+        let port = self.pin.port();
+        self.pin.io().set_port_mode(port, PortMode::InputFloating)?;
+        Ok(TypedPin::new(self.pin))
+    }
+
+    /// Reconfigure this pin as an input with a weak pull-up, consuming it and returning the
+    /// re-typed pin.
+    pub fn into_input_pullup(self) -> Result<TypedPin<'io, IO, PullUp>, IO::Error> {
+        let port = self.pin.port();
+        self.pin.io().set_port_mode(port, PortMode::InputPullup)?;
+        Ok(TypedPin::new(self.pin))
+    }
+}
+
+impl<'io, IO: ReconfigurablePortIO> TypedPin<'io, IO, Floating>
+where
+    IO::Error: crate::hal::digital::Error,
+{
+    /// Read whether the pin is currently at a logic high level.
+    pub fn is_high(&mut self) -> Result<bool, IO::Error> {
+        self.pin.is_high()
+    }
+
+    /// Read whether the pin is currently at a logic low level.
+    pub fn is_low(&mut self) -> Result<bool, IO::Error> {
+        self.pin.is_low()
+    }
+
+    /// Reconfigure this pin as a push-pull output, consuming it and returning the re-typed pin.
+    pub fn into_output(self) -> Result<TypedPin<'io, IO, Output>, IO::Error> {
+        let port = self.pin.port();
+        self.pin.io().set_port_mode(port, PortMode::Output)?;
+        Ok(TypedPin::new(self.pin))
+    }
+
+    /// Reconfigure this pin as an input with a weak pull-up, consuming it and returning the
+    /// re-typed pin.
+    pub fn into_input_pullup(self) -> Result<TypedPin<'io, IO, PullUp>, IO::Error> {
+        let port = self.pin.port();
+        self.pin.io().set_port_mode(port, PortMode::InputPullup)?;
+        Ok(TypedPin::new(self.pin))
+    }
+}
+
+impl<'io, IO: ReconfigurablePortIO> TypedPin<'io, IO, PullUp>
+where
+    IO::Error: crate::hal::digital::Error,
+{
+    /// Read whether the pin is currently at a logic high level.
+    pub fn is_high(&mut self) -> Result<bool, IO::Error> {
+        self.pin.is_high()
+    }
+
+    /// Read whether the pin is currently at a logic low level.
+    pub fn is_low(&mut self) -> Result<bool, IO::Error> {
+        self.pin.is_low()
+    }
+
+    /// Reconfigure this pin as a push-pull output, consuming it and returning the re-typed pin.
+    pub fn into_output(self) -> Result<TypedPin<'io, IO, Output>, IO::Error> {
+        let port = self.pin.port();
+        self.pin.io().set_port_mode(port, PortMode::Output)?;
+        Ok(TypedPin::new(self.pin))
+    }
+
+    /// Reconfigure this pin as a floating input, consuming it and returning the re-typed pin.
+    pub fn into_input_floating(self) -> Result<TypedPin<'io, IO, Floating>, IO::Error> {
+        let port = self.pin.port();
+        self.pin.io().set_port_mode(port, PortMode::InputFloating)?;
+        Ok(TypedPin::new(self.pin))
+    }
+}
@@ -2,7 +2,7 @@
 //! supported electrical/bus interfaces. It is a shim between `embedded-hal` implementations and
 //! the expander's registers.
 
-use registers::RegisterAddress;
+use crate::registers::RegisterAddress;
 
 /// An interface for the MAX7301 implements this trait, which provides the basic operations for
 /// sending pre-encoded register accesses to the chip via the interface.
@@ -15,11 +15,149 @@ pub trait ExpanderInterface {
     fn read_register(&mut self, addr: RegisterAddress) -> Result<u8, Self::Error>;
 }
 
+/// The `async` counterpart of [`ExpanderInterface`], for drivers built on top of
+/// `embedded-hal-async` executors (e.g. Embassy). It offers the same two register-access
+/// primitives, but as `async fn`s so that an implementation backed by an async SPI or I2C bus can
+/// yield to the executor instead of busy-waiting on the transfer.
+#[cfg(feature = "async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncExpanderInterface {
+    /// The type of error that register reads and writes may return.
+    type Error;
+    /// Issue a write command to the expander to write `value` into the register at `addr`.
+    async fn write_register(&mut self, addr: RegisterAddress, value: u8)
+        -> Result<(), Self::Error>;
+    /// Issue a read command to the expander to fetch the `u8` value at register `addr`.
+    async fn read_register(&mut self, addr: RegisterAddress) -> Result<u8, Self::Error>;
+}
+
+/// A hook an `ExpanderInterface` can implement to reassert some bus configuration immediately
+/// before a register access. Borrowed from the `SetConfig`/`*WithConfig` device-wrapper idea used
+/// by `embedded-hal-bus` and embassy's shared-bus adapters: when the MAX7301/MAX7300 shares a bus
+/// with peripherals that need different settings (SPI clock polarity/speed, I2C bus speed, ...),
+/// another peripheral's transaction can leave the bus configured wrong by the time this interface
+/// talks to it next, so the setting has to be reasserted on every access rather than once at
+/// construction. See [`with_config::WithBusConfig`] for the adapter that does this automatically.
+pub trait SetConfig {
+    /// The bus-configuration type understood by this interface.
+    type Config;
+    /// Reassert `config` on the underlying bus. Implementations should make this cheap, since an
+    /// adapter built on this trait calls it before every register access.
+    fn set_config(&mut self, config: &Self::Config);
+}
+
+pub mod with_config {
+    //! An [`ExpanderInterface`] adapter that wraps another interface together with a bus
+    //! configuration value, reasserting it via [`SetConfig`] immediately before every register
+    //! access. See [`SetConfig`] for the motivating shared-bus scenario.
+
+    use super::{ExpanderInterface, RegisterAddress, SetConfig};
+
+    /// Wraps an `ExpanderInterface` that implements [`SetConfig`] together with the configuration
+    /// value to reassert before each access.
+    ///
+    /// Construct one with the interface and config, and hand it to `Expander::new` in place of
+    /// the bare interface: every `Expander` method that reads or writes a register goes through
+    /// `ExpanderInterface::read_register`/`write_register`, so wrapping the interface here is
+    /// enough to cover `read_ports`/`write_ports`/`Configurator::commit`/... without the caller
+    /// reconfiguring the bus manually around each one.
+    pub struct WithBusConfig<EI: SetConfig> {
+        iface: EI,
+        config: EI::Config,
+    }
+
+    impl<EI: SetConfig> WithBusConfig<EI> {
+        /// Create a new adapter from an interface and the bus configuration to reassert before
+        /// each access.
+        pub fn new(iface: EI, config: EI::Config) -> Self {
+            Self { iface, config }
+        }
+
+        /// Replace the bus configuration to reassert on subsequent accesses.
+        pub fn set_bus_config(&mut self, config: EI::Config) {
+            self.config = config;
+        }
+
+        /// Consume the adapter, returning the underlying interface and its last bus configuration.
+        pub fn release(self) -> (EI, EI::Config) {
+            (self.iface, self.config)
+        }
+    }
+
+    impl<EI: ExpanderInterface + SetConfig> ExpanderInterface for WithBusConfig<EI> {
+        type Error = EI::Error;
+
+        fn write_register(&mut self, addr: RegisterAddress, value: u8) -> Result<(), Self::Error> {
+            self.iface.set_config(&self.config);
+            self.iface.write_register(addr, value)
+        }
+
+        fn read_register(&mut self, addr: RegisterAddress) -> Result<u8, Self::Error> {
+            self.iface.set_config(&self.config);
+            self.iface.read_register(addr)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::vec;
+        use std::vec::Vec;
+
+        struct SpyInterface {
+            applied: Vec<u8>,
+            last_register: Option<(bool, u8, u8)>,
+        }
+
+        impl SetConfig for SpyInterface {
+            type Config = u8;
+            fn set_config(&mut self, config: &u8) {
+                self.applied.push(*config);
+            }
+        }
+
+        impl ExpanderInterface for SpyInterface {
+            type Error = core::convert::Infallible;
+            fn write_register(
+                &mut self,
+                addr: RegisterAddress,
+                value: u8,
+            ) -> Result<(), Self::Error> {
+                self.last_register = Some((false, addr.into(), value));
+                Ok(())
+            }
+            fn read_register(&mut self, addr: RegisterAddress) -> Result<u8, Self::Error> {
+                self.last_register = Some((true, addr.into(), 0));
+                Ok(0)
+            }
+        }
+
+        #[test]
+        fn reasserts_config_before_every_access() {
+            let mut with_config = WithBusConfig::new(
+                SpyInterface {
+                    applied: Vec::new(),
+                    last_register: None,
+                },
+                7u8,
+            );
+            with_config
+                .write_register(RegisterAddress(0x04), 0x01)
+                .unwrap();
+            with_config.read_register(RegisterAddress(0x04)).unwrap();
+            with_config.set_bus_config(9);
+            with_config.read_register(RegisterAddress(0x06)).unwrap();
+
+            assert_eq!(with_config.iface.applied, vec![7, 7, 9]);
+        }
+    }
+}
+
 // This is here (and has to be pub) for doctests only. It's useless otherwise.
 #[doc(hidden)]
 pub mod noop {
     use super::ExpanderInterface;
-    use registers::RegisterAddress;
+    use crate::registers::RegisterAddress;
     pub struct NoopInterface;
     impl ExpanderInterface for NoopInterface {
         type Error = core::convert::Infallible;
@@ -36,13 +174,18 @@ pub mod noop {
     }
 }
 
+#[cfg(feature = "hal02")]
 pub mod spi {
     //! The SPI interface controls a MAX7301 via a 4-wire interface (SCK, MOSI, MISO, CS).
+    //!
+    //! This is the legacy `embedded-hal` 0.2 (`v2`) interface, kept for back-compat behind the
+    //! `hal02` feature. New code should prefer [`super::spi_device::SpiDeviceInterface`], which is
+    //! built on `embedded-hal` 1.0's `SpiDevice` and does not need a separate CS pin.
 
-    use hal;
+    use crate::hal02 as hal;
 
     use super::{ExpanderInterface, RegisterAddress};
-    use registers::Register;
+    use crate::registers::Register;
 
     /// The union of all errors that may occur on the SPI interface. This primarily consists of
     /// variants for each of the error types for the chip select GPIO, SPI write, and SPI transfer.
@@ -142,14 +285,478 @@ pub mod spi {
     }
 }
 
+pub mod spi_device {
+    //! An `embedded-hal` 1.0 SPI interface built on `SpiDevice`, which handles CS assertion and
+    //! bus arbitration itself. This is the preferred interface; it allows the MAX7301 to share a
+    //! bus with other peripherals through a `SpiDevice` wrapper (e.g. `embedded-hal-bus`), which
+    //! the CS-toggling [`super::spi::SpiInterface`] cannot do safely.
+
+    use crate::hal::spi::{Operation, SpiDevice};
+
+    use super::{ExpanderInterface, RegisterAddress};
+    use crate::registers::Register;
+
+    /// The union of all errors that may occur on the `SpiDevice`-based interface. Since CS
+    /// assertion and bus arbitration are delegated to the `SpiDevice`, there is no separate
+    /// chip-select error variant here (compare [`super::spi::SpiInterfaceError`]).
+    #[derive(Debug)]
+    pub enum SpiDeviceInterfaceError<E> {
+        /// An error occurred during a bus transaction.
+        TransferError(E),
+        /// A register address was returned by the device that does not match what was sent. This
+        /// is probably a hardware issue.
+        AddressError,
+    }
+
+    /// A configured `ExpanderInterface` for controlling a MAX7301 via a single `SpiDevice`.
+    pub struct SpiDeviceInterface<SPI> {
+        /// The SPI device connected to the MAX7301.
+        spi: SPI,
+    }
+
+    impl<SPI> SpiDeviceInterface<SPI>
+    where
+        SPI: SpiDevice<u8>,
+    {
+        /// Create a new interface to communicate with the port expander. `spi` is an
+        /// `embedded-hal` 1.0 `SpiDevice` already configured for the MAX7301; it may be shared
+        /// with other peripherals on the same bus.
+        pub fn new(spi: SPI) -> Self {
+            Self { spi }
+        }
+    }
+
+    impl<SPI> ExpanderInterface for SpiDeviceInterface<SPI>
+    where
+        SPI: SpiDevice<u8>,
+    {
+        type Error = SpiDeviceInterfaceError<SPI::Error>;
+
+        fn write_register(&mut self, addr: RegisterAddress, value: u8) -> Result<(), Self::Error> {
+            // Address goes in upper byte, value goes in lower. Address MSB is zero for a write.
+            let buf = [u8::from(addr), value];
+            self.spi
+                .transaction(&mut [Operation::Write(&buf)])
+                .map_err(SpiDeviceInterfaceError::TransferError)
+        }
+
+        fn read_register(&mut self, addr: RegisterAddress) -> Result<u8, Self::Error> {
+            // Address goes in upper byte, lower byte is don't-care because it will be clobbered
+            // when CS deasserts at the end of this transaction. Address MSB is *set* for a read.
+            let addr_word = 0x80 | u8::from(addr);
+            self.spi
+                .write(&[addr_word, 0])
+                .map_err(SpiDeviceInterfaceError::TransferError)?;
+
+            // Expander has latched the value of the requested register into the low byte of its
+            // SPI shift register at the end of the previous transaction. Shift in a no-op so the
+            // expander will do nothing on this transaction, and shift the latched value back.
+            //
+            // This has to be a separate `transaction` call from the address write above:
+            // `SpiDevice::transaction` asserts CS once for the whole call and only deasserts it at
+            // the end, but the MAX7301 only latches the addressed register's value on the CS
+            // rising edge between the address phase and the data phase.
+            let mut buf = [RegisterAddress::from(Register::Noop).into(), 0u8];
+            self.spi
+                .transaction(&mut [Operation::TransferInPlace(&mut buf)])
+                .map_err(SpiDeviceInterfaceError::TransferError)?;
+
+            if buf[0] != addr_word {
+                Err(SpiDeviceInterfaceError::AddressError)
+            } else {
+                Ok(buf[1])
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::vec;
+        use std::vec::Vec;
+
+        /// A minimal `SpiDevice` mock that records each top-level `transaction()` call
+        /// separately. The MAX7301 only latches an addressed register's value into its shift
+        /// register on the CS rising edge between the address phase and the data phase, so a
+        /// correct `read_register` must issue those as two distinct `transaction()` calls rather
+        /// than bundling both operations into one (which would hold CS asserted the whole time).
+        struct MockSpiDevice {
+            next_read: u8,
+            last_addr_byte: u8,
+            garble_echo: bool,
+            call_op_counts: Vec<usize>,
+        }
+
+        impl MockSpiDevice {
+            fn new(next_read: u8) -> Self {
+                Self {
+                    next_read,
+                    last_addr_byte: 0,
+                    garble_echo: false,
+                    call_op_counts: Vec::new(),
+                }
+            }
+        }
+
+        impl crate::hal::spi::ErrorType for MockSpiDevice {
+            type Error = core::convert::Infallible;
+        }
+
+        impl SpiDevice<u8> for MockSpiDevice {
+            fn transaction(
+                &mut self,
+                operations: &mut [Operation<'_, u8>],
+            ) -> Result<(), Self::Error> {
+                self.call_op_counts.push(operations.len());
+                for op in operations {
+                    match op {
+                        Operation::Write(data) => self.last_addr_byte = data[0],
+                        Operation::TransferInPlace(buf) => {
+                            buf[0] = if self.garble_echo {
+                                !self.last_addr_byte
+                            } else {
+                                self.last_addr_byte
+                            };
+                            buf[1] = self.next_read;
+                        }
+                        _ => unimplemented!("test mock only exercises Write and TransferInPlace"),
+                    }
+                }
+                Ok(())
+            }
+        }
+
+        #[test]
+        fn read_register_issues_two_separate_cs_bracketed_transactions() {
+            let mut iface = SpiDeviceInterface::new(MockSpiDevice::new(0x42));
+            assert_eq!(
+                iface.read_register(Register::Configuration.into()).unwrap(),
+                0x42
+            );
+            assert_eq!(iface.spi.call_op_counts, vec![1, 1]);
+        }
+
+        #[test]
+        fn read_register_detects_address_echo_mismatch() {
+            let mut mock = MockSpiDevice::new(0x42);
+            mock.garble_echo = true;
+            let mut iface = SpiDeviceInterface::new(mock);
+            assert!(matches!(
+                iface.read_register(Register::Configuration.into()),
+                Err(SpiDeviceInterfaceError::AddressError)
+            ));
+        }
+
+        #[test]
+        fn write_register_issues_one_transaction() {
+            let mut iface = SpiDeviceInterface::new(MockSpiDevice::new(0));
+            iface
+                .write_register(Register::Configuration.into(), 0x01)
+                .unwrap();
+            assert_eq!(iface.spi.call_op_counts, vec![1]);
+        }
+    }
+}
+
+pub mod i2c {
+    //! An I2C interface for the MAX7300, the pin-compatible I2C-bus sibling of the SPI-bus
+    //! MAX7301. The two parts share a register map, so this is just a different transport for the
+    //! same `ExpanderInterface` trait the SPI interface implements; everything built on top of
+    //! `ExpanderInterface` (`Expander`, `Configurator`, `ImmediateIO`, `TransactionalIO`, ...)
+    //! works unchanged over I2C.
+
+    use crate::hal::i2c::I2c;
+
+    use super::{ExpanderInterface, RegisterAddress};
+
+    /// The strap state of one of the MAX7300's `AD0`/`AD1` address pins, which select the part's
+    /// 7-bit I2C slave address.
+    #[derive(Clone, Copy, Debug)]
+    pub enum Strap {
+        /// Pin tied to GND.
+        Gnd,
+        /// Pin tied to VCC.
+        Vcc,
+        /// Pin tied to SDA.
+        Sda,
+        /// Pin tied to SCL.
+        Scl,
+    }
+
+    fn strap_code(strap: Strap) -> u8 {
+        match strap {
+            Strap::Gnd => 0b00,
+            Strap::Scl => 0b01,
+            Strap::Sda => 0b10,
+            Strap::Vcc => 0b11,
+        }
+    }
+
+    /// Compute the MAX7300's 7-bit I2C slave address from the strap state of its `AD0` and `AD1`
+    /// pins, per the device datasheet's address table.
+    pub fn address(ad0: Strap, ad1: Strap) -> u8 {
+        0b0100_0000 | (strap_code(ad1) << 2) | strap_code(ad0)
+    }
+
+    /// A configured `ExpanderInterface` for controlling a MAX7300 via I2C.
+    pub struct I2cInterface<I2C> {
+        /// The I2C master device connected to the MAX7300.
+        i2c: I2C,
+        /// The MAX7300's 7-bit I2C slave address, as computed by [`address`].
+        addr: u8,
+    }
+
+    impl<I2C> I2cInterface<I2C>
+    where
+        I2C: I2c,
+    {
+        /// Create a new I2C interface to communicate with the port expander. `i2c` is the I2C
+        /// master device, and `addr` is the MAX7300's 7-bit slave address (see [`address`]).
+        pub fn new(i2c: I2C, addr: u8) -> Self {
+            Self { i2c, addr }
+        }
+    }
+
+    impl<I2C> ExpanderInterface for I2cInterface<I2C>
+    where
+        I2C: I2c,
+    {
+        type Error = I2C::Error;
+
+        fn write_register(&mut self, addr: RegisterAddress, value: u8) -> Result<(), Self::Error> {
+            // A write transaction is the register address followed by the data byte.
+            self.i2c.write(self.addr, &[u8::from(addr), value])
+        }
+
+        fn read_register(&mut self, addr: RegisterAddress) -> Result<u8, Self::Error> {
+            // A read transaction is a write of the register address, then a repeated-start read
+            // of the data byte.
+            let mut buf = [0u8; 1];
+            self.i2c
+                .write_read(self.addr, &[u8::from(addr)], &mut buf)?;
+            Ok(buf[0])
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+pub mod spi_async {
+    //! The async counterpart of [`super::spi`], for use under `embedded-hal-async` executors. It
+    //! talks to the MAX7301 over a single `SpiDevice`, which is responsible for asserting CS and
+    //! arbitrating the bus, so there is no separate CS pin to manage here.
+
+    use embedded_hal_async::spi::{Operation, SpiDevice};
+
+    use super::{AsyncExpanderInterface, RegisterAddress};
+    use crate::registers::Register;
+
+    /// The union of all errors that may occur on the async SPI interface.
+    #[derive(Debug)]
+    pub enum AsyncSpiInterfaceError<E> {
+        /// An error occurred during a bus transaction.
+        TransferError(E),
+        /// A register address was returned by the device that does not match what was sent. This
+        /// is probably a hardware issue.
+        AddressError,
+    }
+
+    /// A configured [`AsyncExpanderInterface`] for controlling a MAX7301 via SPI under an async
+    /// executor.
+    pub struct AsyncSpiInterface<SPI> {
+        /// The SPI device connected to the MAX7301. CS assertion and bus arbitration are handled
+        /// by the `SpiDevice` implementation, which is what allows this interface to share a bus
+        /// with other peripherals.
+        spi: SPI,
+    }
+
+    impl<SPI> AsyncSpiInterface<SPI>
+    where
+        SPI: SpiDevice<u8>,
+    {
+        /// Create a new async SPI interface to communicate with the port expander. `spi` is an
+        /// `embedded-hal-async` `SpiDevice` already configured for the MAX7301.
+        pub fn new(spi: SPI) -> Self {
+            Self { spi }
+        }
+    }
+
+    impl<SPI> AsyncExpanderInterface for AsyncSpiInterface<SPI>
+    where
+        SPI: SpiDevice<u8>,
+    {
+        type Error = AsyncSpiInterfaceError<SPI::Error>;
+
+        async fn write_register(
+            &mut self,
+            addr: RegisterAddress,
+            value: u8,
+        ) -> Result<(), Self::Error> {
+            // Address goes in upper byte, value goes in lower. Address MSB is zero for a write.
+            let buf = [u8::from(addr), value];
+            self.spi
+                .write(&buf)
+                .await
+                .map_err(AsyncSpiInterfaceError::TransferError)
+        }
+
+        async fn read_register(&mut self, addr: RegisterAddress) -> Result<u8, Self::Error> {
+            // Address goes in upper byte, lower byte is don't-care because it will be clobbered
+            // when CS deasserts at the end of this transaction. Address MSB is *set* for a read.
+            let addr_word = 0x80 | u8::from(addr);
+            self.spi
+                .write(&[addr_word, 0])
+                .await
+                .map_err(AsyncSpiInterfaceError::TransferError)?;
+
+            // Expander has latched the value of the requested register into the low byte of its
+            // SPI shift register at the end of the previous transaction. Shift in a no-op so the
+            // expander will do nothing on this transaction, and shift the latched value back.
+            let mut buf = [RegisterAddress::from(Register::Noop).into(), 0u8];
+            self.spi
+                .transaction(&mut [Operation::TransferInPlace(&mut buf)])
+                .await
+                .map_err(AsyncSpiInterfaceError::TransferError)?;
+
+            if buf[0] != addr_word {
+                Err(AsyncSpiInterfaceError::AddressError)
+            } else {
+                Ok(buf[1])
+            }
+        }
+    }
+}
+
+/// A public, register-level mock `ExpanderInterface` for downstream crates to unit-test their own
+/// drivers and applications built on top of `PortPin`.
+///
+/// Following [`driver-pal`](https://docs.rs/driver-pal)'s `mock` feature, [`MockInterface`]
+/// records the ordered sequence of register reads and writes it receives as a log of
+/// [`Transaction`]s, which a test can inspect with [`MockInterface::transactions`] or assert
+/// against wholesale with [`MockInterface::expect`]. [`MockInterface::preload`] seeds a register
+/// with a starting value (e.g. the chip's power-on reset value) before the code under test runs.
+///
+/// This lets a consumer verify, for example, that their transactional `write_back(Strategy::Exact)`
+/// call collapses to the minimum set of register writes they expect, without needing real
+/// hardware or the crate-internal `test_spy` interface.
+#[cfg(feature = "mock")]
+pub mod mock {
+    use std::sync::{Arc, Mutex};
+    use std::vec::Vec;
+
+    use super::ExpanderInterface;
+    use crate::registers::RegisterAddress;
+
+    /// One entry in a [`MockInterface`]'s transaction log.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Transaction {
+        /// A write of `value` to the register at `addr`.
+        Write {
+            /// The register address that was written.
+            addr: u8,
+            /// The value that was written.
+            value: u8,
+        },
+        /// A read from the register at `addr`, which returned `value`.
+        Read {
+            /// The register address that was read.
+            addr: u8,
+            /// The value that was returned.
+            value: u8,
+        },
+    }
+
+    /// A register-level mock [`ExpanderInterface`], for use in downstream crates' own unit tests.
+    /// See the [module documentation](self) for an overview.
+    #[derive(Clone)]
+    pub struct MockInterface {
+        registers: Arc<Mutex<[u8; 0x60]>>,
+        log: Arc<Mutex<Vec<Transaction>>>,
+    }
+
+    impl MockInterface {
+        /// Create a new mock interface with every register initialized to `0x00`.
+        pub fn new() -> Self {
+            Self {
+                registers: Arc::new(Mutex::new([0u8; 0x60])),
+                log: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+
+        /// Seed the register at `addr` with `value`, without recording a transaction. Use this to
+        /// set up a register's power-on reset value (or any other starting state) before running
+        /// the code under test.
+        pub fn preload(&self, addr: u8, value: u8) {
+            self.registers.lock().unwrap()[addr as usize] = value;
+        }
+
+        /// The ordered sequence of register reads and writes observed so far.
+        pub fn transactions(&self) -> Vec<Transaction> {
+            self.log.lock().unwrap().clone()
+        }
+
+        /// Clear the transaction log, without altering current register contents. Useful for
+        /// discarding the setup/configuration traffic before exercising the code under test.
+        pub fn clear_log(&self) {
+            self.log.lock().unwrap().clear();
+        }
+
+        /// Assert that the transaction log observed so far is exactly `expected`, then clear the
+        /// log.
+        ///
+        /// # Panics
+        ///
+        /// Panics, printing both the expected and actual logs, if they don't match.
+        pub fn expect(&self, expected: &[Transaction]) {
+            let actual = self.transactions();
+            assert_eq!(
+                actual, expected,
+                "MockInterface transaction log did not match expectation"
+            );
+            self.clear_log();
+        }
+    }
+
+    impl Default for MockInterface {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl ExpanderInterface for MockInterface {
+        type Error = core::convert::Infallible;
+
+        fn write_register(&mut self, addr: RegisterAddress, value: u8) -> Result<(), Self::Error> {
+            let enc_addr = u8::from(addr);
+            self.registers.lock().unwrap()[enc_addr as usize] = value;
+            self.log.lock().unwrap().push(Transaction::Write {
+                addr: enc_addr,
+                value,
+            });
+            Ok(())
+        }
+
+        fn read_register(&mut self, addr: RegisterAddress) -> Result<u8, Self::Error> {
+            let enc_addr = u8::from(addr);
+            let value = self.registers.lock().unwrap()[enc_addr as usize];
+            self.log.lock().unwrap().push(Transaction::Read {
+                addr: enc_addr,
+                value,
+            });
+            Ok(value)
+        }
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod test_spy {
     //! An interface for use in unit tests to spy on whatever was sent to it.
 
     use super::ExpanderInterface;
-    use registers::RegisterAddress;
+    use crate::registers::RegisterAddress;
     use std::fmt;
+    use std::format;
     use std::sync::{Arc, Mutex};
+    use std::vec::Vec;
 
     #[derive(Clone, Copy, Debug, PartialEq)]
     pub enum TestRegister {
@@ -276,9 +883,7 @@ pub(crate) mod test_spy {
             assert!(init.len() == 32 - 4);
             Self {
                 ports: Arc::new(Mutex::new(
-                    init.into_iter()
-                        .map(|b| TestPort::Reset(b))
-                        .collect::<Vec<_>>(),
+                    init.into_iter().map(TestPort::Reset).collect::<Vec<_>>(),
                 )),
             }
         }
@@ -2,30 +2,55 @@
 //!
 //! The MAX7301 is a device that provides either 20 or 28 GPIO pins, which are
 //! software-configurable as push-pull output, floating input, or input with weak pull-up. The
-//! state of each pin can be read and written through an SPI serial bus.
+//! state of each pin can be read and written through an SPI serial bus. Its pin-compatible sibling,
+//! the MAX7300, shares the same register map and port model but is addressed over I2C instead; see
+//! [`I2cInterface`] below.
 //!
 //! This driver is intended to work on embedded platforms using any implementation of the
-//! `embedded-hal` trait library. It communicates with the expander via any SPI and GPIO device
-//! implementing the respective traits, and permits creation of new GPIO devices corresponding to
-//! the I/O pins on the device, which themselves implement the HAL traits.
+//! `embedded-hal` trait library. It communicates with the expander via any SPI, I2C, and GPIO
+//! device implementing the respective traits, and permits creation of new GPIO devices
+//! corresponding to the I/O pins on the device, which themselves implement the HAL traits.
 //!
 //! # Construction
 //!
 //! To set up the driver:
 //!
 //! - Use your platform's `embedded-hal` implementation to obtain the necessary I/Os where your
-//!   MAX7301 is connected. For the SPI version (currently the only supported version), you will
-//!   need an SPI master device, and one GPIO push-pull output pin device for chip select.
-//! - Construct an [`ExpanderInterface`] — the [`SpiInterface`] for MAX7301 — which will take
-//!   ownership of the I/O devices you just obtained.
+//!   MAX7301 is connected. For the SPI version, you will need an `embedded-hal` 1.0 `SpiDevice`.
+//! - Construct an [`ExpanderInterface`] — the [`SpiDeviceInterface`] for MAX7301 — which will take
+//!   ownership of the I/O device you just obtained.
 //! - Construct an [`Expander`], which will take ownership of the `ExpanderInterface`, and which will
 //!   provide a builder API to configure the device.
 //!
 //! ```ignore
-//! let spi = /* construct something implementing embedded_hal::spi::blocking::{Write,Transfer} */
-//! let cs = /* construct something implementing embedded_hal::digital::OutputPin */
+//! let spi = /* construct something implementing embedded_hal::spi::SpiDevice */
 //!
-//! let ei = max7301::SpiInterface::new(spi, cs);
+//! let ei = max7301::SpiDeviceInterface::new(spi);
+//! let mut expander = max7301::Expander::new(ei);
+//! ```
+//!
+//! If your platform only has an `embedded-hal` 0.2 (`v2`) SPI master and a separate chip-select
+//! pin, enable the `hal02` feature and use [`SpiInterface`] instead, which toggles CS by hand
+//! around each bus access. The `hal02` feature also switches `PortPin` itself over to the `v2`
+//! digital traits (via an `embedded-hal-0.2` compatibility shim), for platforms whose whole HAL
+//! stack is still on 0.2; by default `PortPin` implements the `embedded-hal` 1.0 digital traits,
+//! including `StatefulOutputPin`, so `is_set_high()`/`toggle()` are available alongside
+//! `is_high()`/`set_high()`.
+//!
+//! If your hardware is a MAX7300 on an I2C bus instead, construct an [`I2cInterface`] from an
+//! `embedded-hal` `I2c` implementation and the device's slave address (computed from its `AD0`/
+//! `AD1` strap state by [`interface::i2c::address`]), and hand that to `Expander::new` in place of
+//! a SPI interface. Everything downstream of `ExpanderInterface` — `Expander`, `Configurator`,
+//! `ImmediateIO`, `TransactionalIO`, and so on — is written against the trait and works unchanged
+//! over either bus; which chip you're talking to is purely a matter of which interface you
+//! construct.
+//!
+//! ```ignore
+//! let i2c = /* construct something implementing embedded_hal::i2c::I2c */
+//! use max7301::interface::i2c::Strap;
+//!
+//! let addr = max7301::interface::i2c::address(Strap::Gnd, Strap::Gnd);
+//! let ei = max7301::I2cInterface::new(i2c, addr);
 //! let mut expander = max7301::Expander::new(ei);
 //! ```
 //!
@@ -37,7 +62,7 @@
 //! configuration registers:
 //!
 //! ```
-//! # fn main() -> Result<(), ()> {
+//! # fn main() -> Result<(), max7301::error::Error<core::convert::Infallible>> {
 //! # let ei = max7301::interface::noop::NoopInterface;
 //! # let mut expander = max7301::Expander::new(ei);
 //! expander
@@ -58,7 +83,7 @@
 //! directly:
 //!
 //! ```
-//! # fn main() -> Result<(), ()> {
+//! # fn main() -> Result<(), max7301::error::Error<core::convert::Infallible>> {
 //! # let ei = max7301::interface::noop::NoopInterface;
 //! # let mut expander = max7301::Expander::new(ei);
 //! let four_thru_twelve: u8 = expander.read_ports(4)?;
@@ -90,11 +115,18 @@
 //!
 //! ```
 //! # struct MyTrafficLight<P>(core::marker::PhantomData<P>);
+//! # #[cfg(not(feature = "hal02"))]
 //! # impl<P> MyTrafficLight<P> where P: embedded_hal::digital::OutputPin {
 //! #   fn new(r: P, y: P, g: P) -> Self { Self(core::marker::PhantomData) }
 //! #   fn change_to_red(&mut self) {}
 //! # }
-//! # fn main() -> Result<(), ()> {
+//! # #[cfg(feature = "hal02")]
+//! # impl<P> MyTrafficLight<P> where P: embedded_hal_0_2::digital::v2::OutputPin {
+//! #   fn new(r: P, y: P, g: P) -> Self { Self(core::marker::PhantomData) }
+//! #   fn change_to_red(&mut self) {}
+//! # }
+//! # #[cfg(feature = "std")]
+//! # fn main() -> Result<(), max7301::error::Error<core::convert::Infallible>> {
 //! # let ei = max7301::interface::noop::NoopInterface;
 //! # let mut expander = max7301::Expander::new(ei);
 //! expander.configure().ports(4..=6, max7301::PortMode::Output).commit()?;
@@ -108,6 +140,8 @@
 //! traffic_light.change_to_red();
 //! # Ok(())
 //! # }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
 //! ```
 //!
 //! In this example, each time `MyTrafficLight` interacts with an `OutputPin` trait method, the
@@ -115,17 +149,36 @@
 //! expander's corresponding output pin. Likewise, if an `InputPin` trait method is called, the
 //! driver will perform a bus transaction to read the current state from the expander pin.
 //!
+//! ### Compile-time pin modes
+//!
+//! *See [`expander::typestate`].*
+//!
+//! `ImmediateIO::port_pin` hands back a `PortPin` whose direction is not checked at all: it
+//! implements both `InputPin` and `OutputPin` regardless of how the port is actually configured.
+//! If you'd rather a misuse of direction be a compile error, `ImmediateIO::output_pin`,
+//! `input_floating_pin`, and `input_pullup_pin` reconfigure the port and return a [`TypedPin`]
+//! that only exposes the methods valid for its mode, with `into_output`/`into_input_floating`/
+//! `into_input_pullup` to reconfigure and re-type it, and `downgrade` to fall back to a plain
+//! `PortPin`.
+//!
 //! ## Transactional mode
 //!
 //! *See [`Expander::into_transactional`] and [`TransactionalIO`].*
 //!
 //! ```
 //! # struct MyFancyTrafficLight<P>(core::marker::PhantomData<P>);
+//! # #[cfg(not(feature = "hal02"))]
 //! # impl<P> MyFancyTrafficLight<P> where P: embedded_hal::digital::OutputPin {
 //! #   fn new(r: P, y: P, g: P, s: P) -> Self { Self(core::marker::PhantomData) }
 //! #   fn change_if_tripped(&mut self) {}
 //! # }
-//! # fn main() -> Result<(), ()> {
+//! # #[cfg(feature = "hal02")]
+//! # impl<P> MyFancyTrafficLight<P> where P: embedded_hal_0_2::digital::v2::OutputPin {
+//! #   fn new(r: P, y: P, g: P, s: P) -> Self { Self(core::marker::PhantomData) }
+//! #   fn change_if_tripped(&mut self) {}
+//! # }
+//! # #[cfg(feature = "std")]
+//! # fn main() -> Result<(), max7301::error::Error<core::convert::Infallible>> {
 //! # let ei = max7301::interface::noop::NoopInterface;
 //! # let mut expander = max7301::Expander::new(ei);
 //! expander
@@ -147,6 +200,8 @@
 //! txn_io.write_back(max7301::Strategy::Exact)?;
 //! # Ok(())
 //! # }
+//! # #[cfg(not(feature = "std"))]
+//! # fn main() {}
 //! ```
 //!
 //! In this example, the transactional API adds two extra methods on the I/O adapter: `refresh()`
@@ -168,6 +223,12 @@
 //!   sensors, LEDs, and indicators. In such a case, the states of the GPIOs are often read or
 //!   updated in a procedure that can be bracketed by `refresh` and `write_back`, since the order
 //!   in which the states are read from or written out to the hardware is not important.
+//! - With the `async` feature, `TransactionalIO::port_pin` also gains `wait_for_high`/
+//!   `wait_for_low`/`wait_for_rising_edge`/`wait_for_falling_edge`, embassy-style futures that
+//!   resolve the next time a `refresh` call observes the condition. Since the MAX7301 has no
+//!   interrupt line for generic GPIO changes, something still has to call `refresh` periodically
+//!   (e.g. a timer task) to drive them; see [`expander::transactional::PortWait`].
+//!
 //! - Transactional mode is not appropriate, and immediate mode should be used instead, for drivers
 //!   or applications that use the generated GPIOs in a "bit-banged" manner, where pins must
 //!   transition states with particular timings or orderings with respect to each other. In
@@ -186,29 +247,100 @@
 //! will be a type alias to `std::sync::Mutex<T>` with a provided impl of `IOMutex`. Similarly, for
 //! Cortex-M environments using the `cortex-m` crate, enabling the `cortexm` Cargo feature will
 //! alias `mutex::DefaultMutex<T>` to `cortex_m::interrupt::Mutex<core::cell::RefCell<T>>` with a
-//! provided `IOMutex` impl. This arrangement should allow you to just specify `DefaultMutex` as in
-//! the examples, and have the correct thing happen based on the build environment.
+//! provided `IOMutex` impl. For any other `no_std` target that has a [`critical-section`] impl
+//! registered (RISC-V, Cortex-A, multi-core targets, ...), the `critical-section` feature aliases
+//! `mutex::DefaultMutex<T>` to [`mutex::CriticalSectionMutex<T>`] instead, without depending on
+//! `cortex_m`. This arrangement should allow you to just specify `DefaultMutex` as in the
+//! examples, and have the correct thing happen based on the build environment.
+//!
+//! If you're running on an [`embassy`](https://embassy.dev) executor and need to share one
+//! `Expander` between tasks rather than interrupt handlers, the `embassy-sync` feature provides
+//! [`mutex::EmbassyMutex<Raw, T>`], an `IOMutex` wrapping `embassy_sync`'s
+//! `blocking_mutex::Mutex<Raw, _>`. It is generic over `Raw: embassy_sync::blocking_mutex::raw::RawMutex`,
+//! so you choose the locking strategy yourself, e.g. `CriticalSectionRawMutex` for cross-core
+//! access or the cheaper `NoopRawMutex`/`ThreadModeRawMutex` when every task shares one executor.
+//!
+//! ## Shared-bus configuration
+//!
+//! *See [`interface::SetConfig`] and [`WithBusConfig`].*
+//!
+//! `IOMutex` arbitrates *access* to a shared bus, but says nothing about bus *settings* (SPI clock
+//! polarity/speed, I2C bus speed, ...) that another peripheral sharing the bus might change
+//! between your accesses. If your bus needs that, wrap the interface in [`WithBusConfig`] with
+//! the configuration to reassert; it calls `SetConfig::set_config` on the inner interface
+//! immediately before every register access, the same way `embedded-hal-bus`'s and embassy's
+//! shared-bus device wrappers do for a plain `embedded-hal` device.
+//!
+//! # Testing your own code
+//!
+//! *See [`interface::mock`].*
+//!
+//! If your code composes `PortPin`s from this driver (directly, or as the GPIOs for some other
+//! `embedded-hal` driver you're using), enable the `mock` feature to get [`MockInterface`], a
+//! register-level mock `ExpanderInterface` you can hand to `Expander::new` in your own unit tests,
+//! then assert on the transaction log it records.
+//!
+//! [`critical-section`]: https://docs.rs/critical-section
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
 #[cfg(feature = "std")]
 extern crate core;
+// The crate's own test suite (`#[cfg(test)]` modules, e.g. `interface::test_spy`) uses `std`
+// collections for convenience regardless of whether a downstream user enables the `std` feature.
+#[cfg(test)]
+extern crate std;
 #[cfg(test)]
 extern crate proptest;
 
 extern crate embedded_hal as hal;
+#[cfg(feature = "hal02")]
+extern crate embedded_hal_0_2 as hal02;
 
 pub mod config;
+pub mod error;
 pub mod expander;
 pub mod interface;
 pub mod mutex;
 pub mod registers;
 
 pub use config::PortMode;
+pub use error::Error;
+pub use expander::cached::CachedIO;
 pub use expander::immediate::ImmediateIO;
-pub use expander::pin::{ExpanderIO, PortPin};
+pub use expander::pin::{ExpanderIO, Pins, PortPin, Variant};
 pub use expander::transactional::{Strategy, TransactionalIO};
+pub use expander::transition::TransitionDispatcher;
+pub use expander::typestate::{Floating, Output, PullUp, ReconfigurablePortIO, TypedPin};
 pub use expander::Expander;
+#[cfg(feature = "hal02")]
 pub use interface::spi::SpiInterface;
-pub use interface::ExpanderInterface;
-pub use mutex::{DefaultMutex, IOMutex};
+pub use interface::i2c::I2cInterface;
+pub use interface::spi_device::SpiDeviceInterface;
+pub use interface::with_config::WithBusConfig;
+pub use interface::{ExpanderInterface, SetConfig};
+pub use mutex::IOMutex;
+
+#[cfg(any(feature = "std", feature = "cortexm", feature = "critical-section"))]
+pub use mutex::DefaultMutex;
+
+#[cfg(feature = "mock")]
+pub use interface::mock::{MockInterface, Transaction};
+
+#[cfg(feature = "embassy-sync")]
+pub use mutex::EmbassyMutex;
+
+#[cfg(feature = "async")]
+pub use config::AsyncConfigurator;
+#[cfg(feature = "async")]
+pub use expander::pin::{AsyncExpanderIO, AsyncPortPin};
+#[cfg(feature = "async")]
+pub use expander::transactional::{AsyncTransactionalIO, PortWait};
+#[cfg(feature = "async")]
+pub use expander::transition_detector::{Edge, TransitionDetector};
+#[cfg(feature = "async")]
+pub use expander::AsyncExpander;
+#[cfg(feature = "async")]
+pub use interface::spi_async::AsyncSpiInterface;
+#[cfg(feature = "async")]
+pub use interface::AsyncExpanderInterface;
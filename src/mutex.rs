@@ -19,13 +19,49 @@ pub trait IOMutex<T> {
     fn lock<R, F: FnOnce(&mut T) -> R>(&self, f: F) -> R;
 }
 
-#[cfg(feature = "std")]
+#[cfg(any(feature = "std", test))]
 pub type DefaultMutex<T> = std::sync::Mutex<T>;
 
-#[cfg(feature = "cortexm")]
+#[cfg(all(feature = "cortexm", not(feature = "std"), not(test)))]
 pub type DefaultMutex<T> = cortex_m::interrupt::Mutex<core::cell::RefCell<T>>;
 
-#[cfg(feature = "std")]
+#[cfg(all(
+    feature = "critical-section",
+    not(feature = "std"),
+    not(feature = "cortexm"),
+    not(test)
+))]
+pub type DefaultMutex<T> = CriticalSectionMutex<T>;
+
+/// An `IOMutex` built on the portable [`critical-section`](https://docs.rs/critical-section)
+/// crate rather than any particular chip's interrupt-masking API. Any target that registers a
+/// `critical-section` implementation (Cortex-M, RISC-V, Cortex-A/Zynq-style, `std`, ...) can use
+/// this, so it is the preferred `DefaultMutex` for code that doesn't want to hard-wire itself to
+/// `cortex_m::interrupt::Mutex`.
+#[cfg(feature = "critical-section")]
+pub struct CriticalSectionMutex<T> {
+    cell: core::cell::RefCell<T>,
+}
+
+// Unsafety: access to `cell` is only ever taken from within `critical_section::with`, which
+// guarantees mutual exclusion with any other critical section on the same core, and (depending on
+// the registered implementation) across cores too.
+#[cfg(feature = "critical-section")]
+unsafe impl<T> Sync for CriticalSectionMutex<T> {}
+
+#[cfg(feature = "critical-section")]
+impl<T> IOMutex<T> for CriticalSectionMutex<T> {
+    fn new(v: T) -> Self {
+        Self {
+            cell: core::cell::RefCell::new(v),
+        }
+    }
+    fn lock<R, F: FnOnce(&mut T) -> R>(&self, f: F) -> R {
+        critical_section::with(|_| f(&mut self.cell.borrow_mut()))
+    }
+}
+
+#[cfg(any(feature = "std", test))]
 impl<T> IOMutex<T> for std::sync::Mutex<T> {
     fn new(v: T) -> Self {
         std::sync::Mutex::new(v)
@@ -48,3 +84,34 @@ impl<T> IOMutex<T> for cortex_m::interrupt::Mutex<core::cell::RefCell<T>> {
         })
     }
 }
+
+/// An `IOMutex` built on `embassy_sync`'s [`blocking_mutex::Mutex`](embassy_sync::blocking_mutex::Mutex),
+/// parameterized over any `embassy_sync` `RawMutex` (e.g. `CriticalSectionRawMutex` for
+/// cross-core locking, or the lighter `ThreadModeRawMutex`/`NoopRawMutex` for single-executor
+/// use).
+///
+/// This is the one to reach for when a single `TransactionalIO` (or `ImmediateIO`, `CachedIO`,
+/// ...) needs to live in a `static` and hand out `PortPin`s to multiple `embassy` tasks on the
+/// same executor: unlike `DefaultMutex`, it doesn't require a single global critical section
+/// (unless you choose `CriticalSectionRawMutex`), and it composes with `embassy`'s own
+/// cooperative scheduling instead of disabling interrupts.
+///
+/// `embassy_sync::blocking_mutex::Mutex::lock` only hands back a shared `&T`, since it's meant to
+/// be paired with a `RefCell` for the mutable case; this alias bakes that `RefCell` in so that
+/// `EmbassyMutex<Raw, T>` satisfies [`IOMutex<T>`] with the same `lock(|v: &mut T| ...)` closure
+/// shape as every other mutex in this module.
+#[cfg(feature = "embassy-sync")]
+pub type EmbassyMutex<Raw, T> = embassy_sync::blocking_mutex::Mutex<Raw, core::cell::RefCell<T>>;
+
+#[cfg(feature = "embassy-sync")]
+impl<Raw, T> IOMutex<T> for embassy_sync::blocking_mutex::Mutex<Raw, core::cell::RefCell<T>>
+where
+    Raw: embassy_sync::blocking_mutex::raw::RawMutex,
+{
+    fn new(v: T) -> Self {
+        embassy_sync::blocking_mutex::Mutex::new(core::cell::RefCell::new(v))
+    }
+    fn lock<R, F: FnOnce(&mut T) -> R>(&self, f: F) -> R {
+        self.lock(|cell| f(&mut cell.borrow_mut()))
+    }
+}
@@ -46,6 +46,20 @@ pub(crate) fn valid_port(port: u8) -> u8 {
     }
 }
 
+/// The first port, of ports 4-31, whose transitions can be monitored by the hardware
+/// transition-detection feature.
+pub(crate) const FIRST_WATCHABLE_PORT: u8 = 24;
+
+/// The last port whose transitions can be monitored by the hardware transition-detection feature.
+pub(crate) const LAST_WATCHABLE_PORT: u8 = 31;
+
+pub(crate) fn valid_transition_port(port: u8) -> u8 {
+    match port {
+        FIRST_WATCHABLE_PORT..=LAST_WATCHABLE_PORT => port,
+        _ => panic!("MAX7301 transition detection is only available on ports 24-31"),
+    }
+}
+
 fn valid_bank(bank: u8) -> u8 {
     match bank {
         0..=6 => bank,
@@ -81,7 +95,7 @@ mod tests {
     #[test]
     #[should_panic]
     fn bank_config_address_invalid() {
-        RegisterAddress::from(Register::BankConfig(7));
+        let _ = RegisterAddress::from(Register::BankConfig(7));
     }
 
     #[test]
@@ -93,7 +107,7 @@ mod tests {
     #[test]
     #[should_panic]
     fn single_port_address_invalid() {
-        RegisterAddress::from(Register::SinglePort(37));
+        let _ = RegisterAddress::from(Register::SinglePort(37));
     }
 
     #[test]
@@ -105,6 +119,6 @@ mod tests {
     #[test]
     #[should_panic]
     fn port_range_address_invalid() {
-        RegisterAddress::from(Register::PortRange(37));
+        let _ = RegisterAddress::from(Register::PortRange(37));
     }
 }